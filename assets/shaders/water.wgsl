@@ -0,0 +1,28 @@
+#import bevy_render::globals::Globals
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+#import "shaders/utils.wgsl"::voro_noise
+
+@group(0) @binding(1) var<uniform> globals: Globals;
+@group(2) @binding(0) var<uniform> material_color: vec4<f32>;
+@group(2) @binding(1) var<uniform> surface_color: vec4<f32>;
+@group(2) @binding(2) var<uniform> size: vec2<f32>;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    // distance from the top of the volume in pixels
+    let depth = mesh.uv.y * size.y;
+    let x = mesh.uv.x * size.x;
+
+    let wave = sin(x * 0.15 + globals.time * 2.0) + 0.5 * sin(x * 0.4 - globals.time * 3.0);
+    let surface = depth + wave - 1.5;
+    if (surface < 0.0) {
+        discard;
+    }
+    if (surface < 1.5) {
+        return surface_color;
+    }
+
+    let shimmer = voro_noise(vec2(x, depth) * 0.05 + globals.time * 0.3, 1.0, 1.0);
+    let darken = clamp(depth / 64.0, 0.0, 0.4);
+    return vec4(material_color.xyz * (1.0 - darken) + shimmer * 0.08, material_color.a);
+}
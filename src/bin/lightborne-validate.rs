@@ -0,0 +1,485 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bevy_ecs_ldtk::{
+    ldtk::{EntityInstance, FieldValue, LayerInstance, LdtkJson, Level, Type},
+    prelude::LdtkFields,
+};
+
+/// The project checked when no path is given.
+const DEFAULT_PROJECT_PATH: &str = "assets/levels/lightborne.ldtk";
+
+/// The strings accepted by `TryFrom<&String> for LightColor`.
+const LIGHT_COLORS: &[&str] = &["Green", "Purple", "White", "Blue", "Black"];
+/// The strings accepted by `TryFrom<&String> for CrystalColor`.
+const CRYSTAL_COLORS: &[&str] = &["Pink", "Red", "White", "Blue"];
+/// The strings accepted by `TryFrom<&String> for BgmTrack`.
+const BGM_TRACKS: &[&str] = &["MustntStop", "LightInTheDark", "Cutscene1Draft", "None"];
+
+/// `Terrain` int grid values of white crystals, which take their id from the `Crystalmap` layer.
+const WHITE_CRYSTAL_VALUES: &[i32] = &[7, 8];
+
+#[derive(Clone, Copy)]
+enum FieldKind {
+    Int,
+    Float,
+    Bool,
+    Point,
+    LightColor,
+    CrystalColor,
+    Enum,
+}
+
+/// The fields every entity needs to have, by entity identifier. An entity missing one of them
+/// spawns as an error marker instead. The project should define each of these entities with their
+/// fields, so that level designers can place them.
+const REQUIRED_FIELDS: &[(&str, &[(&str, FieldKind)])] = &[
+    (
+        "Sensor",
+        &[
+            ("toggle_color", FieldKind::CrystalColor),
+            ("id", FieldKind::Int),
+            ("activation_time", FieldKind::Int),
+        ],
+    ),
+    ("CrystalShard", &[("light_color", FieldKind::LightColor)]),
+    (
+        "MovingPlatform",
+        &[
+            ("speed", FieldKind::Float),
+            ("DefaultState", FieldKind::Enum),
+            ("does_reverse", FieldKind::Bool),
+            ("does_repeat", FieldKind::Bool),
+            ("can_reactivate", FieldKind::Bool),
+            ("event_id", FieldKind::Int),
+        ],
+    ),
+    (
+        "LightSource",
+        &[
+            ("XOffset", FieldKind::Float),
+            ("YOffset", FieldKind::Float),
+            ("Direction", FieldKind::Point),
+            ("BothDirections", FieldKind::Bool),
+        ],
+    ),
+    (
+        "WindZone",
+        &[
+            ("direction", FieldKind::Float),
+            ("strength", FieldKind::Float),
+        ],
+    ),
+    ("Water", &[]),
+    ("Checkpoint", &[]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    /// The game handles this, but it is probably a mistake.
+    Warning,
+    /// The game panics or misbehaves.
+    Error,
+}
+
+/// A problem found in the project, with where it was found.
+#[derive(Debug)]
+struct Problem {
+    severity: Severity,
+    level: String,
+    /// The world coordinates of the problem, if it is in a specific place in the level.
+    position: Option<(i32, i32)>,
+    message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity}: {}", self.level)?;
+        if let Some((x, y)) = self.position {
+            write!(f, " at ({x}, {y})")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Collects the [`Problem`]s of a single level.
+struct LevelReport<'a> {
+    level: &'a Level,
+    name: String,
+    problems: &'a mut Vec<Problem>,
+}
+
+impl LevelReport<'_> {
+    fn push(&mut self, severity: Severity, position: Option<(i32, i32)>, message: String) {
+        self.problems.push(Problem {
+            severity,
+            level: self.name.clone(),
+            position,
+            message,
+        });
+    }
+
+    fn error(&mut self, message: impl Into<String>) {
+        self.push(Severity::Error, None, message.into());
+    }
+
+    fn entity_error(&mut self, entity: &EntityInstance, message: impl Into<String>) {
+        let position = (
+            entity.world_x.unwrap_or(self.level.world_x + entity.px.x),
+            entity.world_y.unwrap_or(self.level.world_y + entity.px.y),
+        );
+        let message = format!("{}: {}", entity.identifier, message.into());
+        self.push(Severity::Error, Some(position), message);
+    }
+}
+
+fn main() -> ExitCode {
+    let mut paths: Vec<PathBuf> = std::env::args().skip(1).map(PathBuf::from).collect();
+    if paths.is_empty() {
+        paths.push(DEFAULT_PROJECT_PATH.into());
+    }
+
+    let mut errors = 0;
+    for path in paths {
+        let problems = match validate_project(&path) {
+            Ok(problems) => problems,
+            Err(err) => {
+                eprintln!("error: {}: {err}", path.display());
+                errors += 1;
+                continue;
+            }
+        };
+        for problem in &problems {
+            println!("{}: {problem}", path.display());
+        }
+        errors += problems
+            .iter()
+            .filter(|problem| problem.severity == Severity::Error)
+            .count();
+    }
+
+    if errors > 0 {
+        eprintln!("{errors} error(s) found");
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Loads an LDtk project, along with its external level files, and validates every level.
+fn validate_project(path: &Path) -> Result<Vec<Problem>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let project: LdtkJson = serde_json::from_str(&contents).map_err(|err| err.to_string())?;
+
+    let mut problems = Vec::new();
+    validate_definitions(&project, &mut problems);
+    for level in &project.levels {
+        match &level.external_rel_path {
+            Some(rel_path) => {
+                let level_path = path.parent().unwrap_or(Path::new("")).join(rel_path);
+                let external_level = std::fs::read_to_string(&level_path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|contents| {
+                        serde_json::from_str::<Level>(&contents).map_err(|err| err.to_string())
+                    });
+                match external_level {
+                    Ok(external_level) => validate_level(&external_level, &mut problems),
+                    Err(err) => problems.push(Problem {
+                        severity: Severity::Error,
+                        level: level.identifier.clone(),
+                        position: None,
+                        message: format!("could not load {}: {err}", level_path.display()),
+                    }),
+                }
+            }
+            None => validate_level(level, &mut problems),
+        }
+    }
+    Ok(problems)
+}
+
+/// Checks that the project defines every entity in [`REQUIRED_FIELDS`] along with its fields.
+fn validate_definitions(project: &LdtkJson, problems: &mut Vec<Problem>) {
+    let mut push = |severity, message| {
+        problems.push(Problem {
+            severity,
+            level: "entity definitions".to_owned(),
+            position: None,
+            message,
+        })
+    };
+    for (identifier, fields) in REQUIRED_FIELDS {
+        let Some(definition) = project
+            .defs
+            .entities
+            .iter()
+            .find(|definition| definition.identifier == *identifier)
+        else {
+            // projects only need the entities their levels use
+            push(Severity::Warning, format!("{identifier} is not defined"));
+            continue;
+        };
+        for (field, _) in fields.iter() {
+            if !definition
+                .field_defs
+                .iter()
+                .any(|field_def| field_def.identifier == *field)
+            {
+                push(
+                    Severity::Error,
+                    format!("{identifier} does not define the field {field}"),
+                );
+            }
+        }
+    }
+}
+
+fn validate_level(level: &Level, problems: &mut Vec<Problem>) {
+    let name = match level.get_string_field("LevelId") {
+        Ok(level_id) => format!("{} (LevelId {level_id})", level.identifier),
+        Err(_) => level.identifier.clone(),
+    };
+    let mut report = LevelReport {
+        level,
+        name,
+        problems,
+    };
+
+    match level.get_string_field("LevelId") {
+        Ok(level_id) if level_id.is_empty() => report.error("LevelId is empty"),
+        Ok(_) => {}
+        Err(_) => report.error("missing LevelId"),
+    }
+    match level.iter_enums_field("AllowedColors") {
+        Ok(colors) => {
+            for color in colors {
+                if !LIGHT_COLORS.contains(&color.as_str()) {
+                    report.error(format!("AllowedColors contains unknown color {color}"));
+                }
+            }
+        }
+        Err(_) => report.error("missing AllowedColors"),
+    }
+    // optional, levels without it play the music of their chapter
+    if let Ok(Some(bgm)) = level.get_maybe_enum_field("Bgm") {
+        if !BGM_TRACKS.contains(&bgm.as_str()) {
+            report.error(format!("Bgm is unknown track {bgm}"));
+        }
+    }
+
+    // optional, levels without it don't allow dashing
+    if level.get_field_instance("AllowDash").is_ok() && level.get_bool_field("AllowDash").is_err() {
+        report.error("AllowDash is not a Bool");
+    }
+
+    let Some(layers) = level.layer_instances.as_ref() else {
+        report.error("level has no layers");
+        return;
+    };
+
+    let entities: Vec<&EntityInstance> = layers
+        .iter()
+        .filter(|layer| layer.layer_instance_type == Type::Entities)
+        .flat_map(|layer| &layer.entity_instances)
+        .collect();
+    if !entities.iter().any(|entity| entity.identifier == "Start") {
+        report.error("missing Start flag");
+    }
+    for entity in entities {
+        validate_entity(entity, &mut report);
+    }
+
+    validate_white_crystals(layers, &mut report);
+}
+
+fn validate_entity(entity: &EntityInstance, report: &mut LevelReport) {
+    if let Some((_, fields)) = REQUIRED_FIELDS
+        .iter()
+        .find(|(identifier, _)| *identifier == entity.identifier)
+    {
+        for &(field, kind) in fields.iter() {
+            if let Err(message) = check_field(entity, field, kind) {
+                report.entity_error(entity, message);
+            }
+        }
+    }
+
+    if entity.identifier == "MovingPlatform" {
+        validate_platform_path(entity, report);
+    }
+}
+
+fn check_field(entity: &EntityInstance, field: &str, kind: FieldKind) -> Result<(), String> {
+    let missing = || format!("missing {field}");
+    match kind {
+        FieldKind::Int => entity
+            .get_int_field(field)
+            .map(|_| ())
+            .map_err(|_| missing()),
+        FieldKind::Float => entity
+            .get_float_field(field)
+            .map(|_| ())
+            .map_err(|_| missing()),
+        FieldKind::Bool => entity
+            .get_bool_field(field)
+            .map(|_| ())
+            .map_err(|_| missing()),
+        FieldKind::Point => entity
+            .get_point_field(field)
+            .map(|_| ())
+            .map_err(|_| missing()),
+        FieldKind::Enum => entity
+            .get_enum_field(field)
+            .map(|_| ())
+            .map_err(|_| missing()),
+        FieldKind::LightColor | FieldKind::CrystalColor => {
+            let value = entity.get_enum_field(field).map_err(|_| missing())?;
+            let valid = match kind {
+                FieldKind::LightColor => LIGHT_COLORS,
+                _ => CRYSTAL_COLORS,
+            };
+            if valid.contains(&value.as_str()) {
+                Ok(())
+            } else {
+                Err(format!("{field} has unknown color {value}"))
+            }
+        }
+    }
+}
+
+fn validate_platform_path(platform: &EntityInstance, report: &mut LevelReport) {
+    let path_len = match platform
+        .get_field_instance("path")
+        .map(|field| &field.value)
+    {
+        Ok(FieldValue::Points(points)) => points.iter().flatten().count(),
+        _ => {
+            report.entity_error(platform, "missing path");
+            return;
+        }
+    };
+    let curve_points = match platform
+        .get_field_instance("path_curve_points")
+        .map(|field| &field.value)
+    {
+        Ok(FieldValue::Bools(curve_points)) => curve_points,
+        _ => {
+            report.entity_error(platform, "missing path_curve_points");
+            return;
+        }
+    };
+    let does_reverse = platform
+        .get_bool_field("does_reverse")
+        .copied()
+        .unwrap_or(false);
+
+    for message in path_curve_problems(path_len, curve_points, does_reverse) {
+        report.entity_error(platform, message);
+    }
+}
+
+/// Finds the combinations of `path` and `path_curve_points` that `MovingPlatform` can't follow.
+fn path_curve_problems(path_len: usize, curve_points: &[bool], does_reverse: bool) -> Vec<String> {
+    let mut problems = Vec::new();
+    if path_len == 0 {
+        problems.push("path has no points".to_owned());
+    }
+    if curve_points.is_empty() {
+        return problems;
+    }
+    if curve_points.len() < path_len {
+        problems.push(format!(
+            "path_curve_points has {} values but path has {path_len} points",
+            curve_points.len()
+        ));
+    }
+    if does_reverse && curve_points.last() == Some(&true) {
+        problems.push(
+            "last element of path_curve_points cannot be a curve if the platform reverses"
+                .to_owned(),
+        );
+    }
+    if let Some(index) = curve_points.windows(2).position(|pair| pair[0] && pair[1]) {
+        problems.push(format!(
+            "path_curve_points {index} and {} are adjacent curves",
+            index + 1
+        ));
+    }
+    problems
+}
+
+/// Warns about white crystals without an id in the `Crystalmap` layer. They get id 0, which is
+/// fine for levels with a single group of white crystals.
+fn validate_white_crystals(layers: &[LayerInstance], report: &mut LevelReport) {
+    let find_layer = |identifier: &str| layers.iter().find(|layer| layer.identifier == identifier);
+    let Some(terrain) = find_layer("Terrain") else {
+        report.error("missing Terrain layer");
+        return;
+    };
+    let crystal_map = find_layer("Crystalmap");
+
+    for (i, value) in terrain.int_grid_csv.iter().enumerate() {
+        if !WHITE_CRYSTAL_VALUES.contains(value) {
+            continue;
+        }
+        let crystal_id = crystal_map.and_then(|layer| layer.int_grid_csv.get(i));
+        if crystal_id.is_some_and(|id| *id != 0) {
+            continue;
+        }
+        let grid = (i as i32 % terrain.c_wid, i as i32 / terrain.c_wid);
+        let position = (
+            report.level.world_x + grid.0 * terrain.grid_size,
+            report.level.world_y + grid.1 * terrain.grid_size,
+        );
+        report.push(
+            Severity::Warning,
+            Some(position),
+            "white crystal has no Crystalmap id, so it gets id 0".to_owned(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn illegal_curve_points_are_reported() {
+        assert!(path_curve_problems(3, &[], true).is_empty());
+        assert!(path_curve_problems(3, &[false, true, false], true).is_empty());
+        assert_eq!(path_curve_problems(3, &[false, true, true], false).len(), 1);
+        assert_eq!(path_curve_problems(3, &[false, false, true], true).len(), 1);
+        // too few values for the path
+        assert_eq!(path_curve_problems(3, &[true, false], false).len(), 1);
+    }
+
+    #[test]
+    fn shipped_levels_have_no_errors() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_PROJECT_PATH);
+        let problems = validate_project(&path).unwrap();
+        let errors: Vec<_> = problems
+            .iter()
+            .filter(|problem| problem.severity == Severity::Error)
+            .map(ToString::to_string)
+            .collect();
+        assert!(errors.is_empty(), "{errors:#?}");
+    }
+
+    #[test]
+    fn shipped_project_defines_every_entity() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_PROJECT_PATH);
+        let contents = std::fs::read_to_string(path).unwrap();
+        let project: LdtkJson = serde_json::from_str(&contents).unwrap();
+        let mut problems = Vec::new();
+        validate_definitions(&project, &mut problems);
+        let problems: Vec<_> = problems.iter().map(ToString::to_string).collect();
+        assert!(problems.is_empty(), "{problems:#?}");
+    }
+}
@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+/// [`Resource`] holding the command line arguments Lightborne was started with. Arguments that
+/// aren't recognized are ignored, so that they can be handled by other plugins such as
+/// `bevy_mod_debugdump`.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct CliArgs {
+    /// `--record <file>`: records the input of the session into the file, see
+    /// [`Recording`](crate::input::replay::Recording).
+    pub record: Option<PathBuf>,
+    /// `--replay <file>`: plays back a recording instead of using live input.
+    pub replay: Option<PathBuf>,
+    /// `--set <section.field=value>`: overrides a value of the [`Config`](crate::config::Config).
+    /// Can be passed several times.
+    pub config_overrides: Vec<String>,
+    /// `--level <LevelId>`: skips the start menu and starts at the `Start` flag of the level.
+    pub level: Option<String>,
+    /// `--config <file>`: loads the config from the file instead of `Lightborne.toml`.
+    pub config: Option<PathBuf>,
+    /// `--unlock-all`: unlocks every level, like `--set debug_config.unlock_levels=true`.
+    pub unlock_all: bool,
+    /// `--mute`: starts with the volume at zero, without changing the saved settings.
+    pub mute: bool,
+    /// `--windowed <W>x<H>`: the size of the window, in logical pixels.
+    pub windowed: Option<Vec2>,
+    /// Problems with the arguments, logged by [`log_cli_warnings`] since logging isn't set up yet
+    /// when the arguments are parsed.
+    pub warnings: Vec<String>,
+}
+
+impl CliArgs {
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut cli_args = CliArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => cli_args.record = args.next().map(PathBuf::from),
+                "--replay" => cli_args.replay = args.next().map(PathBuf::from),
+                "--set" => cli_args.config_overrides.extend(args.next()),
+                "--level" => cli_args.level = args.next(),
+                "--config" => cli_args.config = args.next().map(PathBuf::from),
+                "--unlock-all" => cli_args.unlock_all = true,
+                "--mute" => cli_args.mute = true,
+                "--windowed" => {
+                    let size = args.next().unwrap_or_default();
+                    cli_args.windowed = parse_window_size(&size);
+                    if cli_args.windowed.is_none() {
+                        cli_args.warnings.push(format!(
+                            "Ignoring --windowed {size}, which should look like 1280x720"
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        cli_args
+    }
+}
+
+/// [`Startup`] [`System`] that logs the [`CliArgs::warnings`].
+pub fn log_cli_warnings(cli_args: Res<CliArgs>) {
+    for warning in &cli_args.warnings {
+        warn!("{warning}");
+    }
+}
+
+/// Parses a window size written as `<W>x<H>`.
+fn parse_window_size(size: &str) -> Option<Vec2> {
+    let (width, height) = size.split_once('x')?;
+    let size = Vec2::new(width.trim().parse().ok()?, height.trim().parse().ok()?);
+    (size.x > 0.0 && size.y > 0.0).then_some(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> CliArgs {
+        CliArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags_are_parsed() {
+        let args = parse(&[
+            "--level",
+            "2B",
+            "--unlock-all",
+            "--windowed",
+            "1280x720",
+            "--dump-render",
+            "render.dot",
+        ]);
+        assert_eq!(args.level.as_deref(), Some("2B"));
+        assert!(args.unlock_all);
+        assert!(!args.mute);
+        assert_eq!(args.windowed, Some(Vec2::new(1280.0, 720.0)));
+    }
+
+    #[test]
+    fn invalid_window_sizes_are_rejected() {
+        assert_eq!(parse_window_size("1280"), None);
+        assert_eq!(parse_window_size("0x720"), None);
+        assert_eq!(parse_window_size("wide x tall"), None);
+        assert_eq!(parse(&["--windowed", "1280"]).warnings.len(), 1);
+    }
+}
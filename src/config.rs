@@ -1,62 +1,476 @@
+use std::collections::BTreeMap;
+
 use bevy::prelude::*;
-use serde::Deserialize;
-use bevy::prelude::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::CliArgs,
+    input::action::{InputAction, InputBinding, InputBindings},
+    save::storage,
+};
+
+/// The file the bindings changed in the settings are stored in, next to the save files.
+const BINDINGS_FILE_NAME: &str = "bindings.toml";
+
+/// The user's config file, relative to the working directory.
+const CONFIG_PATH: &str = "Lightborne.toml";
 
+/// The prefix of environment variables that override config values. The rest of the name is the
+/// path to the value with `__` between the section and the field, e.g.
+/// `LIGHTBORNE_DEBUG_CONFIG__UNLOCK_LEVELS=true`. Only the section and field are case-insensitive,
+/// keys below them keep their case, e.g. `LIGHTBORNE_CONTROLS_CONFIG__BINDINGS__MoveUp`.
+const ENV_PREFIX: &str = "LIGHTBORNE_";
+
+/// The single key fields `controls_config` had before every action got its own bindings, and the
+/// action each of them maps to.
+const LEGACY_KEY_FIELDS: [(&str, InputAction); 5] = [
+    ("key_up", InputAction::MoveUp),
+    ("key_down", InputAction::MoveDown),
+    ("key_left", InputAction::MoveLeft),
+    ("key_right", InputAction::MoveRight),
+    ("key_jump", InputAction::Jump),
+];
+
+/// [`Plugin`] that loads the [`Config`]. Values are layered, with later layers overriding earlier
+/// ones: the built-in defaults, then [`CONFIG_PATH`] or the file passed with `--config`, then the
+/// [`StoredBindings`], then environment variables (see [`ENV_PREFIX`]), then
+/// `--set section.field=value` command line flags. Invalid values and unknown keys are logged and
+/// skipped without affecting the rest of their layer.
 pub struct ConfigPlugin;
 
 impl Plugin for ConfigPlugin {
     fn build(&self, app: &mut App) {
-        let config: Config = match std::fs::read_to_string("Lightborne.toml") {
-            Ok(contents) => toml::from_str(&contents).expect("Failed to parse Lightborne.toml"),
-            Err(_) => Config::default(),
-        };
-        app.insert_resource(config);
+        let args = app
+            .world()
+            .get_resource::<CliArgs>()
+            .cloned()
+            .unwrap_or_default();
+        let mut cli_overrides = args.config_overrides;
+        if args.unlock_all {
+            // first, so that a `--set` of the same value still wins
+            cli_overrides.insert(0, "debug_config.unlock_levels=true".to_owned());
+        }
+
+        let mut layers = Vec::new();
+        // only the default config file is optional
+        let config_optional = args.config.is_none();
+        let config_path = args.config.unwrap_or_else(|| CONFIG_PATH.into());
+        let config_name = config_path.display().to_string();
+        match std::fs::read_to_string(&config_path) {
+            Ok(contents) => match parse_file_layer(&contents) {
+                Ok(layer) => layers.push((config_name, layer)),
+                Err(err) => error!("Ignoring {config_name}: {err}"),
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && config_optional => {}
+            Err(err) => error!("Could not read {config_name}: {err}"),
+        }
+        let stored_bindings = StoredBindings::load();
+        match stored_bindings.layer() {
+            Ok(layer) => layers.push(("stored bindings".to_owned(), layer)),
+            Err(err) => error!("Ignoring stored bindings: {err}"),
+        }
+        layers.push((
+            "environment variables".to_owned(),
+            env_layer(std::env::vars()),
+        ));
+        layers.push(("command line".to_owned(), cli_layer(&cli_overrides)));
+
+        app.insert_resource(build_config(layers))
+            .insert_resource(stored_bindings);
     }
 }
 
-#[derive(Deserialize, Resource)]
+#[derive(Deserialize, Resource, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub level_config: LevelConfig,
     pub debug_config: DebugConfig,
     pub controls_config: ControlsConfig,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Config {
-            level_config: LevelConfig {
-                level_path: "levels/lightborne.ldtk".into(),
-            },
-            debug_config: DebugConfig::default(),
-            controls_config: ControlsConfig {
-                // Movement
-                key_up: KeyCode::KeyW,
-                key_down: KeyCode::KeyS,
-                key_left: KeyCode::KeyA,
-                key_right: KeyCode::KeyD,
-                key_jump: KeyCode::Space,
-            },
-        }
-    }
-}
-
 #[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct DebugConfig {
     pub ui: bool,
     pub unlock_levels: bool,
 }
 
 #[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct LevelConfig {
     pub level_path: String,
 }
 
-#[derive(Deserialize)]
+impl Default for LevelConfig {
+    fn default() -> Self {
+        LevelConfig {
+            level_path: "levels/lightborne.ldtk".into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct ControlsConfig {
-    // Movement
-    pub key_up: KeyCode,
-    pub key_down: KeyCode,
-    pub key_right: KeyCode,
-    pub key_left: KeyCode,
-    pub key_jump: KeyCode,
-}
\ No newline at end of file
+    /// Bindings for every [`InputAction`](crate::input::action::InputAction), missing actions use
+    /// their default bindings
+    pub bindings: InputBindings,
+}
+
+/// [`Resource`] holding the bindings of the actions the player rebound in the settings. Only these
+/// are stored in [`BINDINGS_FILE_NAME`], so the other actions keep following the config.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct StoredBindings(pub BTreeMap<InputAction, Vec<InputBinding>>);
+
+impl StoredBindings {
+    fn load() -> Self {
+        let contents = match storage::read(BINDINGS_FILE_NAME) {
+            Ok(Some(contents)) => contents,
+            Ok(None) => return StoredBindings::default(),
+            Err(err) => {
+                error!("Could not read stored bindings: {err}");
+                return StoredBindings::default();
+            }
+        };
+        toml::from_str(&contents).unwrap_or_else(|err| {
+            error!("Ignoring stored bindings: {err}");
+            StoredBindings::default()
+        })
+    }
+
+    /// The stored bindings as a config layer.
+    fn layer(&self) -> Result<toml::Table, toml::ser::Error> {
+        let mut layer = toml::Table::new();
+        set_path(
+            &mut layer,
+            &["controls_config", "bindings"],
+            toml::Value::try_from(self)?,
+        );
+        Ok(layer)
+    }
+
+    /// Writes the stored bindings to [`BINDINGS_FILE_NAME`], or removes it if there are none.
+    pub fn save(&self) {
+        let result = if self.0.is_empty() {
+            storage::remove(BINDINGS_FILE_NAME)
+        } else {
+            match toml::to_string(self) {
+                Ok(contents) => storage::write(BINDINGS_FILE_NAME, &contents),
+                Err(err) => {
+                    error!("Could not serialize bindings: {err}");
+                    return;
+                }
+            }
+        };
+        if let Err(err) = result {
+            error!("Could not save bindings: {err}");
+        }
+    }
+}
+
+/// Parses the config file into a layer. Only a file that isn't valid TOML is rejected as a whole,
+/// values that don't fit the [`Config`] are skipped one by one in [`build_config`].
+fn parse_file_layer(contents: &str) -> Result<toml::Table, toml::de::Error> {
+    toml::from_str(contents)
+}
+
+/// Builds a layer from every environment variable starting with [`ENV_PREFIX`].
+fn env_layer(vars: impl IntoIterator<Item = (String, String)>) -> toml::Table {
+    let mut layer = toml::Table::new();
+    for (name, value) in vars {
+        let Some(path) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let path = path
+            .split("__")
+            .enumerate()
+            .map(|(i, segment)| match i {
+                0 | 1 => segment.to_lowercase(),
+                _ => segment.to_owned(),
+            })
+            .collect::<Vec<_>>();
+        let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+        set_path(&mut layer, &path, parse_value(&value));
+    }
+    layer
+}
+
+/// Builds a layer from `section.field=value` overrides passed on the command line.
+fn cli_layer(overrides: &[String]) -> toml::Table {
+    let mut layer = toml::Table::new();
+    for config_override in overrides {
+        let Some((path, value)) = config_override.split_once('=') else {
+            warn!("Ignoring --set {config_override}, which should look like section.field=value");
+            continue;
+        };
+        let path = path.trim().split('.').collect::<Vec<_>>();
+        set_path(&mut layer, &path, parse_value(value.trim()));
+    }
+    layer
+}
+
+/// Parses a value given as text as TOML, treating it as a plain string if it isn't valid TOML so
+/// that strings don't need to be quoted.
+fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_owned()))
+}
+
+/// Sets the value at `path` in `table`, creating the tables along the way.
+fn set_path(table: &mut toml::Table, path: &[&str], value: toml::Value) {
+    let Some((key, rest)) = path.split_first() else {
+        return;
+    };
+    if rest.is_empty() {
+        table.insert((*key).to_owned(), value);
+        return;
+    }
+    let entry = table
+        .entry(*key)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    if !entry.is_table() {
+        *entry = toml::Value::Table(toml::Table::new());
+    }
+    if let toml::Value::Table(inner) = entry {
+        set_path(inner, rest, value);
+    }
+}
+
+/// Collects every value in `table` that isn't a table itself, along with its path.
+fn leaf_values(
+    table: toml::Table,
+    path: &mut Vec<String>,
+    leaves: &mut Vec<(Vec<String>, toml::Value)>,
+) {
+    for (key, value) in table {
+        path.push(key);
+        match value {
+            toml::Value::Table(inner) => leaf_values(inner, path, leaves),
+            value => leaves.push((path.clone(), value)),
+        }
+        path.pop();
+    }
+}
+
+/// Replaces the [`LEGACY_KEY_FIELDS`] in a layer with the keyboard bindings of their actions,
+/// keeping the other default bindings of the action. Actions the layer binds itself are left alone.
+fn migrate_legacy_keys(layer: &mut toml::Table, source: &str) {
+    let Some(toml::Value::Table(controls)) = layer.get_mut("controls_config") else {
+        return;
+    };
+    for (field, action) in LEGACY_KEY_FIELDS {
+        let Some(key) = controls.remove(field) else {
+            continue;
+        };
+        let Ok(toml::Value::String(action_name)) = toml::Value::try_from(action) else {
+            continue;
+        };
+        warn!(
+            "controls_config.{field} from {source} is deprecated, use \
+             controls_config.bindings.{action_name} instead"
+        );
+        let mut bindings = match toml::Value::try_from(InputBindings::default().get(action)) {
+            Ok(toml::Value::Array(bindings)) => bindings,
+            _ => Vec::new(),
+        };
+        bindings.retain(|binding| binding.get("Key").is_none());
+        bindings.insert(
+            0,
+            toml::Value::Table(toml::Table::from_iter([("Key".to_owned(), key)])),
+        );
+
+        let entry = controls
+            .entry("bindings")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let toml::Value::Table(layer_bindings) = entry {
+            layer_bindings
+                .entry(action_name)
+                .or_insert(toml::Value::Array(bindings));
+        }
+    }
+}
+
+/// Merges the layers in order. A layer that would make the config invalid is merged again one
+/// value at a time, skipping the invalid values, so that a typo only resets the value it is in.
+fn build_config(layers: Vec<(String, toml::Table)>) -> Config {
+    let mut merged = toml::Table::new();
+    for (source, mut layer) in layers {
+        migrate_legacy_keys(&mut layer, &source);
+        let mut leaves = Vec::new();
+        leaf_values(layer, &mut Vec::new(), &mut leaves);
+
+        let mut candidate = merged.clone();
+        for (path, value) in &leaves {
+            let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+            set_path(&mut candidate, &path, value.clone());
+        }
+        if toml::Value::Table(candidate.clone())
+            .try_into::<Config>()
+            .is_ok()
+        {
+            merged = candidate;
+            continue;
+        }
+
+        for (path, value) in leaves {
+            let mut candidate = merged.clone();
+            let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+            set_path(&mut candidate, &path, value);
+            match toml::Value::Table(candidate.clone()).try_into::<Config>() {
+                Ok(_) => merged = candidate,
+                Err(err) => error!("Ignoring {} from {source}: {err}", path.join(".")),
+            }
+        }
+    }
+    toml::Value::Table(merged).try_into().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_sections_use_defaults() {
+        let config: Config = toml::from_str("[debug_config]\nui = true").unwrap();
+        assert!(config.debug_config.ui);
+        assert!(!config.debug_config.unlock_levels);
+        assert_eq!(config.level_config.level_path, "levels/lightborne.ldtk");
+    }
+
+    #[test]
+    fn file_errors_have_line_numbers() {
+        let err = parse_file_layer("[debug_config]\nui = true\nunlock_levels =").unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{err}");
+    }
+
+    #[test]
+    fn invalid_values_only_skip_themselves() {
+        let file = parse_file_layer(
+            "[debug_config]\nui = true\nunlock_levels = 3\n[controls_config.bindings]\nMoveUp = [{ Key = \"KeyI\" }]\nJump = [{ Key = \"NotAKey\" }]",
+        )
+        .unwrap();
+        let config = build_config(vec![("file".to_owned(), file)]);
+        assert!(config.debug_config.ui);
+        assert!(!config.debug_config.unlock_levels);
+        let bindings = &config.controls_config.bindings;
+        assert_eq!(
+            bindings.get(InputAction::MoveUp),
+            [InputBinding::Key(KeyCode::KeyI)]
+        );
+        assert_eq!(
+            bindings.get(InputAction::Jump),
+            InputBindings::default().get(InputAction::Jump)
+        );
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let file = parse_file_layer(
+            "[debug_config]\nui = true\nunlock_levels = false\n[level_config]\nlevel_path = \"a.ldtk\"",
+        )
+        .unwrap();
+        let env = env_layer([
+            (
+                "LIGHTBORNE_DEBUG_CONFIG__UNLOCK_LEVELS".to_owned(),
+                "true".to_owned(),
+            ),
+            ("PATH".to_owned(), "/usr/bin".to_owned()),
+        ]);
+        let cli = cli_layer(&["level_config.level_path=b.ldtk".to_owned()]);
+
+        let config = build_config(vec![
+            ("file".to_owned(), file),
+            ("env".to_owned(), env),
+            ("cli".to_owned(), cli),
+        ]);
+        assert!(config.debug_config.ui);
+        assert!(config.debug_config.unlock_levels);
+        assert_eq!(config.level_config.level_path, "b.ldtk");
+    }
+
+    #[test]
+    fn invalid_layers_are_skipped() {
+        let config = build_config(vec![
+            (
+                "file".to_owned(),
+                parse_file_layer("[debug_config]\nui = true").unwrap(),
+            ),
+            (
+                "cli".to_owned(),
+                cli_layer(&["debug_config.ui=maybe".to_owned()]),
+            ),
+        ]);
+        assert!(config.debug_config.ui);
+    }
+
+    #[test]
+    fn stored_bindings_only_override_rebound_actions() {
+        let file = parse_file_layer(
+            "[controls_config.bindings]\nMoveUp = [{ Key = \"KeyI\" }]\nJump = [{ Key = \"KeyK\" }]",
+        )
+        .unwrap();
+        let mut stored_bindings = StoredBindings::default();
+        stored_bindings
+            .0
+            .insert(InputAction::Jump, vec![InputBinding::Key(KeyCode::KeyJ)]);
+
+        let config = build_config(vec![
+            ("file".to_owned(), file),
+            ("stored".to_owned(), stored_bindings.layer().unwrap()),
+        ]);
+        let bindings = &config.controls_config.bindings;
+        assert_eq!(
+            bindings.get(InputAction::MoveUp),
+            [InputBinding::Key(KeyCode::KeyI)]
+        );
+        assert_eq!(
+            bindings.get(InputAction::Jump),
+            [InputBinding::Key(KeyCode::KeyJ)]
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_skipped() {
+        let file = parse_file_layer("[debug_config]\nui = true\nunlock_level = true").unwrap();
+        let config = build_config(vec![("file".to_owned(), file)]);
+        assert!(config.debug_config.ui);
+        assert!(!config.debug_config.unlock_levels);
+    }
+
+    #[test]
+    fn legacy_keys_map_to_bindings() {
+        let file = parse_file_layer(
+            "[controls_config]\nkey_up = \"KeyI\"\nkey_jump = \"KeyK\"\n[controls_config.bindings]\nJump = [{ Key = \"KeyJ\" }]",
+        )
+        .unwrap();
+        let config = build_config(vec![("file".to_owned(), file)]);
+        let bindings = &config.controls_config.bindings;
+        let move_up = bindings.get(InputAction::MoveUp);
+        assert_eq!(move_up[0], InputBinding::Key(KeyCode::KeyI));
+        // the gamepad bindings are kept
+        assert_eq!(
+            &move_up[1..],
+            &InputBindings::default().get(InputAction::MoveUp)[1..]
+        );
+        // the new bindings win over the legacy ones
+        assert_eq!(
+            bindings.get(InputAction::Jump),
+            [InputBinding::Key(KeyCode::KeyJ)]
+        );
+    }
+
+    #[test]
+    fn env_vars_keep_the_case_of_keys() {
+        let env = env_layer([(
+            "LIGHTBORNE_CONTROLS_CONFIG__BINDINGS__MoveUp".to_owned(),
+            "[{ Key = \"KeyI\" }]".to_owned(),
+        )]);
+        let config = build_config(vec![("env".to_owned(), env)]);
+        assert_eq!(
+            config.controls_config.bindings.get(InputAction::MoveUp),
+            [InputBinding::Key(KeyCode::KeyI)]
+        );
+    }
+}
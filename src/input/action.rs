@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+
+use bevy::{
+    input::{mouse::MouseWheel, InputSystem},
+    prelude::*,
+    utils::HashSet,
+};
+use enum_map::{enum_map, Enum, EnumMap};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, StoredBindings};
+
+use super::replay::live_input;
+
+/// [`Plugin`] that turns raw keyboard, mouse and gamepad input into [`InputAction`]s using the
+/// [`InputBindings`] from the [`Config`].
+pub struct InputActionPlugin;
+
+impl Plugin for InputActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionState>()
+            .init_resource::<TickInput>()
+            .init_resource::<PendingInput>()
+            .init_resource::<InputRebinding>()
+            .add_systems(
+                PreUpdate,
+                (
+                    capture_rebinding,
+                    update_action_state,
+                    collect_pending_input,
+                )
+                    .chain()
+                    .after(InputSystem)
+                    .run_if(live_input),
+            )
+            .add_systems(FixedPreUpdate, update_tick_input.run_if(live_input));
+    }
+}
+
+/// Everything the player can do with a button. Systems should read these through the
+/// [`ActionState`] instead of reading [`KeyCode`]s or [`MouseButton`]s directly, so that every
+/// control can be rebound.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Enum, Serialize, Deserialize,
+)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Dash,
+    Sneak,
+    Shoot,
+    CancelShot,
+    SnapAim,
+    ColorGreen,
+    ColorPurple,
+    ColorWhite,
+    ColorBlue,
+    ColorBlack,
+    ColorPrev,
+    ColorNext,
+    Reset,
+    LevelSelect,
+    LevelPacks,
+    Settings,
+    Pause,
+}
+
+impl InputAction {
+    /// The name shown for this action in the controls settings.
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            InputAction::MoveUp => "Up",
+            InputAction::MoveDown => "Down / Crouch",
+            InputAction::MoveLeft => "Left",
+            InputAction::MoveRight => "Right",
+            InputAction::Jump => "Jump",
+            InputAction::Dash => "Dash",
+            InputAction::Sneak => "Sneak",
+            InputAction::Shoot => "Aim / Shoot",
+            InputAction::CancelShot => "Cancel Shot",
+            InputAction::SnapAim => "Snap Aim",
+            InputAction::ColorGreen => "Green Light",
+            InputAction::ColorPurple => "Purple Light",
+            InputAction::ColorWhite => "White Light",
+            InputAction::ColorBlue => "Blue Light",
+            InputAction::ColorBlack => "Black Light",
+            InputAction::ColorPrev => "Previous Light",
+            InputAction::ColorNext => "Next Light",
+            InputAction::Reset => "Reset",
+            InputAction::LevelSelect => "Level Select",
+            InputAction::LevelPacks => "Level Packs",
+            InputAction::Settings => "Settings",
+            InputAction::Pause => "Pause",
+        }
+    }
+}
+
+/// [`Resource`] holding the state of every [`InputAction`], updated in [`PreUpdate`] from the
+/// raw input. Because this is a [`ButtonInput`], the usual input run conditions work with it, e.g.
+/// `input_just_pressed(InputAction::Jump)`.
+pub type ActionState = ButtonInput<InputAction>;
+
+/// [`Resource`] holding the input of the current [`FixedUpdate`] tick, updated in
+/// [`FixedPreUpdate`]. Systems that change the simulation run in [`FixedUpdate`] and read this
+/// instead of the [`ActionState`], so that every press is handled by exactly one tick no matter how
+/// many ticks run in a frame. This is what makes replays deterministic.
+#[derive(Resource, Default)]
+pub struct TickInput {
+    pub actions: ActionState,
+    /// The vertical distance of every scroll wheel event since the last tick.
+    pub scroll: Vec<f32>,
+}
+
+/// Sets the [`ActionState`] to exactly the given held, just pressed and just released actions. An
+/// action that was tapped between two updates is just pressed and just released without being held.
+pub fn set_actions(
+    actions: &mut ActionState,
+    pressed: impl Fn(InputAction) -> bool,
+    just_pressed: impl Fn(InputAction) -> bool,
+    just_released: impl Fn(InputAction) -> bool,
+) {
+    actions.reset_all();
+    for action in (0..InputAction::LENGTH).map(InputAction::from_usize) {
+        let pressed = pressed(action);
+        let just_pressed = just_pressed(action);
+        let just_released = just_released(action);
+        if pressed || just_pressed || just_released {
+            actions.press(action);
+        }
+        if !pressed {
+            actions.release(action);
+        }
+        if !just_pressed {
+            actions.clear_just_pressed(action);
+        }
+        if !just_released {
+            actions.clear_just_released(action);
+        }
+    }
+}
+
+/// Run condition that is true on the tick the action was pressed, like
+/// [`input_just_pressed`](bevy::input::common_conditions::input_just_pressed) for the
+/// [`TickInput`].
+pub fn tick_just_pressed(action: InputAction) -> impl FnMut(Res<TickInput>) -> bool + Clone {
+    move |input: Res<TickInput>| input.actions.just_pressed(action)
+}
+
+/// Run condition that is true on every tick the action is held.
+pub fn tick_pressed(action: InputAction) -> impl FnMut(Res<TickInput>) -> bool + Clone {
+    move |input: Res<TickInput>| input.actions.pressed(action)
+}
+
+/// Run condition that is true on the tick the action was released.
+pub fn tick_just_released(action: InputAction) -> impl FnMut(Res<TickInput>) -> bool + Clone {
+    move |input: Res<TickInput>| input.actions.just_released(action)
+}
+
+/// How far a gamepad stick has to be pushed along an axis for a
+/// [`InputBinding::GamepadAxis`] to count as pressed.
+const GAMEPAD_AXIS_THRESHOLD: f32 = 0.5;
+
+/// The direction along a [`GamepadAxis`] that triggers an [`InputBinding::GamepadAxis`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+/// A single key, mouse button, gamepad button or gamepad stick direction that triggers an
+/// [`InputAction`]. Gamepad bindings apply to every connected gamepad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+    GamepadAxis(GamepadAxis, AxisDirection),
+}
+
+impl InputBinding {
+    fn pressed(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+        gamepads: &[&Gamepad],
+    ) -> bool {
+        match self {
+            InputBinding::Key(key) => keys.pressed(*key),
+            InputBinding::Mouse(button) => mouse.pressed(*button),
+            InputBinding::Gamepad(button) => {
+                gamepads.iter().any(|gamepad| gamepad.pressed(*button))
+            }
+            InputBinding::GamepadAxis(axis, direction) => gamepads.iter().any(|gamepad| {
+                let value = gamepad.get(*axis).unwrap_or(0.0);
+                match direction {
+                    AxisDirection::Positive => value > GAMEPAD_AXIS_THRESHOLD,
+                    AxisDirection::Negative => value < -GAMEPAD_AXIS_THRESHOLD,
+                }
+            }),
+        }
+    }
+
+    /// A short human readable name for the binding, e.g. `W` or `Mouse Left`.
+    pub fn display_name(&self) -> String {
+        match self {
+            InputBinding::Key(KeyCode::Space) => "Space".to_owned(),
+            InputBinding::Key(key) => {
+                let name = format!("{key:?}");
+                name.strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name)
+                    .to_owned()
+            }
+            InputBinding::Mouse(button) => format!("Mouse {button:?}"),
+            InputBinding::Gamepad(button) => format!("Pad {button:?}"),
+            InputBinding::GamepadAxis(axis, direction) => {
+                let sign = match direction {
+                    AxisDirection::Positive => "+",
+                    AxisDirection::Negative => "-",
+                };
+                format!("Pad {axis:?}{sign}")
+            }
+        }
+    }
+}
+
+/// The [`InputBinding`]s of every [`InputAction`]. Actions missing from the config keep their
+/// default bindings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "HashMap<InputAction, Vec<InputBinding>>")]
+pub struct InputBindings(EnumMap<InputAction, Vec<InputBinding>>);
+
+impl InputBindings {
+    pub fn get(&self, action: InputAction) -> &[InputBinding] {
+        &self.0[action]
+    }
+
+    /// Replaces every binding of the action with the new binding.
+    pub fn rebind(&mut self, action: InputAction, binding: InputBinding) {
+        self.0[action] = vec![binding];
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use InputBinding::{Gamepad as Pad, GamepadAxis as Stick, Key, Mouse};
+        InputBindings(enum_map! {
+            InputAction::MoveUp => vec![
+                Key(KeyCode::KeyW),
+                Pad(GamepadButton::DPadUp),
+                Stick(GamepadAxis::LeftStickY, AxisDirection::Positive),
+            ],
+            InputAction::MoveDown => vec![
+                Key(KeyCode::KeyS),
+                Pad(GamepadButton::DPadDown),
+                Stick(GamepadAxis::LeftStickY, AxisDirection::Negative),
+            ],
+            InputAction::MoveLeft => vec![
+                Key(KeyCode::KeyA),
+                Pad(GamepadButton::DPadLeft),
+                Stick(GamepadAxis::LeftStickX, AxisDirection::Negative),
+            ],
+            InputAction::MoveRight => vec![
+                Key(KeyCode::KeyD),
+                Pad(GamepadButton::DPadRight),
+                Stick(GamepadAxis::LeftStickX, AxisDirection::Positive),
+            ],
+            InputAction::Jump => vec![Key(KeyCode::Space), Pad(GamepadButton::South)],
+            InputAction::Dash => vec![Key(KeyCode::KeyC), Pad(GamepadButton::North)],
+            InputAction::Sneak => vec![Key(KeyCode::ShiftLeft), Key(KeyCode::ShiftRight)],
+            InputAction::Shoot => vec![
+                Mouse(MouseButton::Left),
+                Pad(GamepadButton::West),
+                Pad(GamepadButton::RightTrigger2),
+            ],
+            InputAction::CancelShot => vec![Mouse(MouseButton::Right), Pad(GamepadButton::East)],
+            InputAction::SnapAim => vec![
+                Key(KeyCode::ShiftLeft),
+                Key(KeyCode::ShiftRight),
+                Pad(GamepadButton::LeftTrigger2),
+            ],
+            InputAction::ColorGreen => vec![Key(KeyCode::Digit1)],
+            InputAction::ColorPurple => vec![Key(KeyCode::Digit2)],
+            InputAction::ColorWhite => vec![Key(KeyCode::Digit3)],
+            InputAction::ColorBlue => vec![Key(KeyCode::Digit4)],
+            InputAction::ColorBlack => vec![Key(KeyCode::Digit5)],
+            InputAction::ColorPrev => vec![Pad(GamepadButton::LeftTrigger)],
+            InputAction::ColorNext => vec![Pad(GamepadButton::RightTrigger)],
+            InputAction::Reset => vec![Key(KeyCode::KeyR), Pad(GamepadButton::Select)],
+            InputAction::LevelSelect => vec![Key(KeyCode::KeyL)],
+            InputAction::LevelPacks => vec![Key(KeyCode::KeyP)],
+            InputAction::Settings => vec![Key(KeyCode::Comma)],
+            InputAction::Pause => vec![Key(KeyCode::Escape), Pad(GamepadButton::Start)],
+        })
+    }
+}
+
+impl From<HashMap<InputAction, Vec<InputBinding>>> for InputBindings {
+    fn from(overrides: HashMap<InputAction, Vec<InputBinding>>) -> Self {
+        let mut bindings = InputBindings::default();
+        for (action, action_bindings) in overrides {
+            bindings.0[action] = action_bindings;
+        }
+        bindings
+    }
+}
+
+/// [`Resource`] set to the [`InputAction`] being rebound. The next key, mouse button or gamepad
+/// button pressed becomes its binding, and no actions are triggered in the meantime. Escape cancels.
+#[derive(Resource, Default)]
+pub struct InputRebinding(pub Option<InputAction>);
+
+fn capture_rebinding(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    q_gamepads: Query<&Gamepad>,
+    mut rebinding: ResMut<InputRebinding>,
+    mut config: ResMut<Config>,
+    mut stored_bindings: ResMut<StoredBindings>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::Escape) {
+        rebinding.0 = None;
+        return;
+    }
+    let binding = keys
+        .get_just_pressed()
+        .next()
+        .map(|key| InputBinding::Key(*key))
+        .or_else(|| {
+            mouse
+                .get_just_pressed()
+                .next()
+                .map(|button| InputBinding::Mouse(*button))
+        })
+        .or_else(|| {
+            q_gamepads.iter().find_map(|gamepad| {
+                gamepad
+                    .get_just_pressed()
+                    .next()
+                    .map(|button| InputBinding::Gamepad(*button))
+            })
+        });
+    if let Some(binding) = binding {
+        config.controls_config.bindings.rebind(action, binding);
+        stored_bindings.0.insert(action, vec![binding]);
+        stored_bindings.save();
+        rebinding.0 = None;
+    }
+}
+
+/// [`System`] that updates the [`ActionState`] from the raw keyboard, mouse and gamepad input.
+pub fn update_action_state(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    q_gamepads: Query<&Gamepad>,
+    config: Res<Config>,
+    rebinding: Res<InputRebinding>,
+    mut actions: ResMut<ActionState>,
+) {
+    actions.clear();
+    let gamepads = q_gamepads.iter().collect::<Vec<_>>();
+    // the key pressed to finish rebinding shouldn't trigger anything either, so buttons pressed
+    // while rebinding only count once they are released and pressed again
+    let capturing = rebinding.0.is_some() || rebinding.is_changed();
+    for action in (0..InputAction::LENGTH).map(InputAction::from_usize) {
+        let pressed = config
+            .controls_config
+            .bindings
+            .get(action)
+            .iter()
+            .any(|binding| binding.pressed(&keys, &mouse, &gamepads));
+        if pressed {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+        if capturing {
+            actions.clear_just_pressed(action);
+        }
+    }
+}
+
+/// [`Resource`] holding the input of the frames since the last [`FixedUpdate`] tick, which goes into
+/// the next [`TickInput`]. Frames without a tick would otherwise lose their presses, releases and
+/// scrolling.
+#[derive(Resource, Default)]
+pub struct PendingInput {
+    just_pressed: HashSet<InputAction>,
+    just_released: HashSet<InputAction>,
+    scroll: Vec<f32>,
+}
+
+fn collect_pending_input(
+    actions: Res<ActionState>,
+    mut pending: ResMut<PendingInput>,
+    mut ev_scroll: EventReader<MouseWheel>,
+) {
+    pending
+        .just_pressed
+        .extend(actions.get_just_pressed().copied());
+    pending
+        .just_released
+        .extend(actions.get_just_released().copied());
+    pending
+        .scroll
+        .extend(ev_scroll.read().map(|scroll| scroll.y));
+}
+
+/// [`System`] that hands the input collected since the last tick to the [`TickInput`] of this
+/// tick. Later ticks of the same frame only see the held actions.
+pub fn update_tick_input(
+    actions: Res<ActionState>,
+    mut pending: ResMut<PendingInput>,
+    mut input: ResMut<TickInput>,
+) {
+    set_actions(
+        &mut input.actions,
+        |action| actions.pressed(action),
+        |action| pending.just_pressed.contains(&action),
+        |action| pending.just_released.contains(&action),
+    );
+    pending.just_pressed.clear();
+    pending.just_released.clear();
+    input.scroll = std::mem::take(&mut pending.scroll);
+}
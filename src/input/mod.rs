@@ -1,7 +1,10 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
-use crate::camera::MainCamera;
+use crate::{camera::MainCamera, player::PlayerMarker};
+
+pub mod action;
+pub mod replay;
 
 /// [`Component`] that holds the position of the cursor, in world coordinates. You should query
 /// for this [`Component`] if you need the cursor position to do something. Note that if your
@@ -40,3 +43,48 @@ pub fn update_cursor_world_coords(
     };
     world_coords.pos = cursor_ray.origin.truncate();
 }
+
+/// How far the right stick has to be pushed for it to take over aiming from the mouse.
+const AIM_STICK_DEADZONE: f32 = 0.3;
+
+/// [`Resource`] holding the normalized direction the player is aiming the light beam in, or
+/// [`Vec2::ZERO`] if they aren't aiming anywhere. Aiming follows the cursor until the right stick
+/// of a gamepad is used, and goes back to the cursor once the mouse moves.
+#[derive(Resource, Default)]
+pub struct AimDirection(pub Vec2);
+
+/// [`Update`] [`System`] that updates the [`AimDirection`] from the cursor or the right stick.
+/// Should run after [`update_cursor_world_coords`].
+pub fn update_aim_direction(
+    mut aim: ResMut<AimDirection>,
+    q_coords: Query<&CursorWorldCoords>,
+    q_player: Query<&Transform, With<PlayerMarker>>,
+    q_gamepads: Query<&Gamepad>,
+    mut ev_cursor_moved: EventReader<CursorMoved>,
+    mut using_stick: Local<bool>,
+) {
+    let stick = q_gamepads
+        .iter()
+        .map(|gamepad| gamepad.right_stick())
+        .find(|stick| stick.length() > AIM_STICK_DEADZONE);
+    if let Some(stick) = stick {
+        *using_stick = true;
+        aim.0 = stick.normalize();
+        return;
+    }
+    if ev_cursor_moved.read().count() > 0 {
+        *using_stick = false;
+    }
+    // keep aiming where the stick was last pointed once it is let go
+    if *using_stick {
+        return;
+    }
+
+    let Ok(coords) = q_coords.get_single() else {
+        return;
+    };
+    let Ok(player_transform) = q_player.get_single() else {
+        return;
+    };
+    aim.0 = (coords.pos - player_transform.translation.truncate()).normalize_or_zero();
+}
@@ -0,0 +1,651 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{app::RunFixedMainLoopSystem, prelude::*};
+use bevy_ecs_ldtk::{prelude::*, LdtkProjectHandle};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{camera_position_from_level, CameraControlType, CameraMoveEvent},
+    cli::CliArgs,
+    level::{get_ldtk_level_data, level_box_from_level, CurrentLevel},
+    particle::Wind,
+    player::{light::BeamAim, tuning::MovementTuning, PlayerMarker},
+    shared::GameState,
+};
+
+use super::{
+    action::{set_actions, ActionState, InputAction, TickInput},
+    AimDirection,
+};
+
+/// How many ticks are recorded between saves of the [`Recording`], so that a crash doesn't lose
+/// the whole session.
+const SAVE_INTERVAL_TICKS: usize = 600;
+
+/// [`Plugin`] that records the input of a session with `--record <file>`, or plays one back with
+/// `--replay <file>`, see [`CliArgs`].
+///
+/// The [`TickInput`] is recorded once per [`FixedUpdate`] tick and fed back one tick at a time, so
+/// the simulation sees the same input on the same tick no matter how fast the replay renders. Only
+/// input that goes through the [`TickInput`] and the [`AimDirection`] is recorded; clicking on menu
+/// buttons is not. The [`BeamAim`] and [`MovementTuning`] are recorded as well, so the replay
+/// doesn't depend on the settings or tuning of the machine playing it back.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(args) = app.world().get_resource::<CliArgs>().cloned() else {
+            return;
+        };
+
+        if let Some(path) = args.replay {
+            if args.record.is_some() {
+                warn!("--record is ignored while replaying");
+            }
+            let recording = match Recording::load(&path) {
+                Ok(recording) => recording,
+                Err(err) => {
+                    error!("Could not load replay {}: {err}", path.display());
+                    return;
+                }
+            };
+            app.insert_resource(Replay {
+                recording,
+                tick: None,
+                started: false,
+                frame: RecordedTick::default(),
+            })
+            .add_systems(FixedPreUpdate, feed_replay_input.run_if(replay_running))
+            .add_systems(
+                RunFixedMainLoop,
+                update_replay_action_state
+                    .in_set(RunFixedMainLoopSystem::AfterFixedMainLoop)
+                    .run_if(replay_running),
+            )
+            .add_systems(
+                Update,
+                start_replay
+                    .run_if(in_state(GameState::Ui))
+                    .run_if(resource_exists::<Replay>.and(not(replay_started))),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                sync_replay_start.run_if(replay_started.and(not(replay_running))),
+            );
+        } else if let Some(path) = args.record {
+            app.insert_resource(Recorder {
+                path,
+                recording: None,
+                tuning: MovementTuning::default(),
+            })
+            .add_systems(OnEnter(GameState::Playing), start_recording)
+            .add_systems(FixedPostUpdate, record_tick.run_if(recording_started))
+            .add_systems(Last, save_recording.run_if(recording_started));
+        }
+    }
+}
+
+/// Everything needed to play back a session: where the player started, the seed of the [`Wind`],
+/// the [`MovementTuning`] and the input of every [`FixedUpdate`] tick since the player first
+/// entered [`GameState::Playing`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Recording {
+    /// The [`LevelIid`] of the first level the player was in. The replay refuses to start if the
+    /// level isn't in the loaded project.
+    pub level_iid: String,
+    pub start_position: Vec2,
+    pub seed: u32,
+    /// The elapsed [`FixedUpdate`] time when the recording started, used to line up the gusts of
+    /// the [`Wind`].
+    pub wind_time: f32,
+    /// The [`MovementTuning`] when the recording started. Later changes are recorded on the tick
+    /// they took effect, see [`RecordedTick::tuning`].
+    pub tuning: MovementTuning,
+    pub ticks: Vec<RecordedTick>,
+}
+
+/// The input of a single [`FixedUpdate`] tick of a [`Recording`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RecordedTick {
+    pub pressed: Vec<InputAction>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub just_pressed: Vec<InputAction>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub just_released: Vec<InputAction>,
+    pub aim: Vec2,
+    /// The [`BeamAim`] at the end of the tick, which depends on the snap angle and aim assist
+    /// settings.
+    pub beam_aim: Vec2,
+    /// The vertical distance of every scroll wheel event.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scroll: Vec<f32>,
+    /// The new [`MovementTuning`], if it changed right before this tick.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tuning: Option<MovementTuning>,
+}
+
+impl RecordedTick {
+    /// Sets the [`ActionState`] to exactly what it was on this tick, including which actions were
+    /// just pressed or released.
+    fn apply(&self, actions: &mut ActionState) {
+        set_actions(
+            actions,
+            |action| self.pressed.contains(&action),
+            |action| self.just_pressed.contains(&action),
+            |action| self.just_released.contains(&action),
+        );
+    }
+
+    /// Adds the input of a later tick, keeping the presses and releases of both.
+    fn merge(&mut self, later: &RecordedTick) {
+        self.pressed.clone_from(&later.pressed);
+        for action in &later.just_pressed {
+            if !self.just_pressed.contains(action) {
+                self.just_pressed.push(*action);
+            }
+        }
+        for action in &later.just_released {
+            if !self.just_released.contains(action) {
+                self.just_released.push(*action);
+            }
+        }
+    }
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, RecordingError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(ron::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RecordingError> {
+        // keep every frame on its own line
+        let pretty = ron::ser::PrettyConfig::new().depth_limit(2);
+        fs::write(path, ron::ser::to_string_pretty(self, pretty)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not access recording: {err}"),
+            Self::Parse(err) => write!(f, "could not parse recording: {err}"),
+            Self::Serialize(err) => write!(f, "could not serialize recording: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::de::SpannedError> for RecordingError {
+    fn from(err: ron::de::SpannedError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<ron::Error> for RecordingError {
+    fn from(err: ron::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+/// [`Resource`] holding the [`Recording`] being played back. It is removed once the replay is
+/// over, which hands control back to the live input.
+#[derive(Resource)]
+pub struct Replay {
+    recording: Recording,
+    /// The index of the next [`RecordedTick`], set once the replay enters [`GameState::Playing`].
+    tick: Option<usize>,
+    /// Whether the player has been moved to the start of the recording.
+    started: bool,
+    /// The input of the ticks that ran this frame, for the systems that read the [`ActionState`].
+    frame: RecordedTick,
+}
+
+/// Run condition that is true unless a [`Replay`] is being played back. Systems reading live input
+/// should use this so they don't fight the replay.
+pub fn live_input(replay: Option<Res<Replay>>) -> bool {
+    replay.is_none()
+}
+
+fn replay_started(replay: Option<Res<Replay>>) -> bool {
+    replay.is_some_and(|replay| replay.started)
+}
+
+fn replay_running(replay: Option<Res<Replay>>) -> bool {
+    replay.is_some_and(|replay| replay.tick.is_some())
+}
+
+/// [`System`] that moves the player to the start of the [`Replay`] once the level has loaded, the
+/// same way the level select does.
+#[allow(clippy::too_many_arguments)]
+fn start_replay(
+    mut replay: ResMut<Replay>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    q_ldtk: Query<&LdtkProjectHandle>,
+    mut q_player: Query<&mut Transform, With<PlayerMarker>>,
+    mut ev_move_camera: EventWriter<CameraMoveEvent>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    let Ok(ldtk_handle) = q_ldtk.get_single() else {
+        return;
+    };
+    let Ok(levels) = get_ldtk_level_data(ldtk_assets.into_inner(), ldtk_handle) else {
+        return;
+    };
+    let Ok(mut player_transform) = q_player.get_single_mut() else {
+        return;
+    };
+
+    let Some(level) = levels
+        .iter()
+        .find(|level| level.iid == replay.recording.level_iid)
+    else {
+        error!(
+            "Could not replay, level {} is not in this project",
+            replay.recording.level_iid
+        );
+        commands.remove_resource::<Replay>();
+        return;
+    };
+    let start = replay.recording.start_position;
+    let level_box = level_box_from_level(level);
+    if !level_box.contains(start) {
+        error!(
+            "Could not replay, the start of the recording is outside of level {}",
+            level.identifier
+        );
+        commands.remove_resource::<Replay>();
+        return;
+    }
+
+    player_transform.translation.x = start.x;
+    player_transform.translation.y = start.y;
+    ev_move_camera.send(CameraMoveEvent {
+        to: camera_position_from_level(level_box, start),
+        variant: CameraControlType::Instant,
+    });
+    // don't trigger the camera transition, see handle_level_selection
+    current_level.level_iid = LevelIid::new("");
+    commands.insert_resource(Wind::with_seed(replay.recording.seed));
+    commands.insert_resource(replay.recording.tuning.clone());
+    next_game_state.set(GameState::Playing);
+
+    info!(
+        "Replaying {} ticks starting in level {}",
+        replay.recording.ticks.len(),
+        level.identifier
+    );
+    replay.started = true;
+}
+
+/// [`System`] that runs when the replay enters [`GameState::Playing`] for the first time, and
+/// lines up the fixed timestep and the [`Wind`] with the recording.
+fn sync_replay_start(
+    mut replay: ResMut<Replay>,
+    mut fixed: ResMut<Time<Fixed>>,
+    mut wind: ResMut<Wind>,
+) {
+    let overstep = fixed.overstep();
+    fixed.discard_overstep(overstep);
+    wind.time_offset = replay.recording.wind_time - fixed.elapsed_secs();
+    replay.tick = Some(0);
+}
+
+/// [`System`] that fills the [`TickInput`], [`AimDirection`] and [`BeamAim`] from the next
+/// [`RecordedTick`], in place of the live input systems.
+fn feed_replay_input(
+    mut replay: ResMut<Replay>,
+    mut input: ResMut<TickInput>,
+    mut actions: ResMut<ActionState>,
+    mut aim: ResMut<AimDirection>,
+    mut beam_aim: ResMut<BeamAim>,
+    mut tuning: ResMut<MovementTuning>,
+    mut commands: Commands,
+) {
+    let replay = replay.as_mut();
+    let Some(index) = replay.tick else {
+        return;
+    };
+    let Some(tick) = replay.recording.ticks.get(index) else {
+        info!("Replay finished, switching back to live input");
+        // update_replay_action_state won't run anymore, so hand over the ticks of this frame here
+        replay.frame.apply(&mut actions);
+        commands.remove_resource::<Replay>();
+        return;
+    };
+    tick.apply(&mut input.actions);
+    input.scroll.clone_from(&tick.scroll);
+    aim.0 = tick.aim;
+    beam_aim.0 = tick.beam_aim;
+    if let Some(new_tuning) = &tick.tuning {
+        *tuning = new_tuning.clone();
+    }
+    replay.frame.merge(tick);
+    replay.tick = Some(index + 1);
+}
+
+/// [`System`] that runs after the [`FixedUpdate`] ticks of a frame, and fills the [`ActionState`]
+/// with the input of all of them, so that systems outside of [`FixedUpdate`] see every press once.
+fn update_replay_action_state(mut replay: ResMut<Replay>, mut actions: ResMut<ActionState>) {
+    replay.frame.apply(&mut actions);
+    replay.frame.just_pressed.clear();
+    replay.frame.just_released.clear();
+}
+
+/// [`Resource`] holding the [`Recording`] of the current session, which starts once the player
+/// first enters [`GameState::Playing`].
+#[derive(Resource)]
+pub struct Recorder {
+    path: PathBuf,
+    recording: Option<Recording>,
+    /// The last recorded [`MovementTuning`], to tell when it changes.
+    tuning: MovementTuning,
+}
+
+fn recording_started(recorder: Res<Recorder>) -> bool {
+    recorder.recording.is_some()
+}
+
+fn start_recording(
+    mut recorder: ResMut<Recorder>,
+    mut fixed: ResMut<Time<Fixed>>,
+    wind: Res<Wind>,
+    tuning: Res<MovementTuning>,
+    q_player: Query<&Transform, With<PlayerMarker>>,
+) {
+    if recorder.recording.is_some() {
+        return;
+    }
+    let Ok(player_transform) = q_player.get_single() else {
+        return;
+    };
+    // the replay can't reproduce the time left over from the menus, so both throw it away
+    let overstep = fixed.overstep();
+    fixed.discard_overstep(overstep);
+    recorder.recording = Some(Recording {
+        level_iid: String::new(),
+        start_position: player_transform.translation.truncate(),
+        seed: wind.seed(),
+        wind_time: fixed.elapsed_secs() + wind.time_offset,
+        tuning: tuning.clone(),
+        ticks: Vec::new(),
+    });
+    recorder.tuning = tuning.clone();
+    info!("Recording input to {}", recorder.path.display());
+}
+
+/// [`System`] that records a [`RecordedTick`] at the end of every [`FixedUpdate`] tick, once the
+/// [`BeamAim`] for it is known.
+fn record_tick(
+    mut recorder: ResMut<Recorder>,
+    input: Res<TickInput>,
+    aim: Res<AimDirection>,
+    beam_aim: Res<BeamAim>,
+    tuning: Res<MovementTuning>,
+    current_level: Res<CurrentLevel>,
+) {
+    let recorder = recorder.as_mut();
+    let Some(recording) = recorder.recording.as_mut() else {
+        return;
+    };
+    let tuning_changed = recorder.tuning != *tuning;
+    if tuning_changed {
+        recorder.tuning = tuning.clone();
+    }
+    // the level select clears the current level, so it is only known a frame later
+    if recording.level_iid.is_empty() {
+        recording.level_iid = current_level.level_iid.as_str().to_owned();
+    }
+    recording.ticks.push(RecordedTick {
+        pressed: input.actions.get_pressed().copied().collect(),
+        just_pressed: input.actions.get_just_pressed().copied().collect(),
+        just_released: input.actions.get_just_released().copied().collect(),
+        aim: aim.0,
+        beam_aim: beam_aim.0,
+        scroll: input.scroll.clone(),
+        tuning: tuning_changed.then(|| tuning.clone()),
+    });
+}
+
+/// [`System`] that writes the [`Recording`] to disk periodically and when the game exits.
+fn save_recording(
+    recorder: Res<Recorder>,
+    mut ev_exit: EventReader<AppExit>,
+    mut saved_ticks: Local<usize>,
+) {
+    let Some(recording) = recorder.recording.as_ref() else {
+        return;
+    };
+    let exiting = ev_exit.read().count() > 0;
+    if !exiting && recording.ticks.len() < *saved_ticks + SAVE_INTERVAL_TICKS {
+        return;
+    }
+    *saved_ticks = recording.ticks.len();
+    if let Err(err) = recording.save(&recorder.path) {
+        error!(
+            "Could not save recording to {}: {err}",
+            recorder.path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_round_trips() {
+        let recording = Recording {
+            level_iid: "level".to_owned(),
+            start_position: Vec2::new(10.0, -20.0),
+            seed: 3,
+            wind_time: 1.5,
+            tuning: MovementTuning::default(),
+            ticks: vec![RecordedTick {
+                pressed: vec![InputAction::Jump, InputAction::MoveLeft],
+                just_pressed: vec![InputAction::Jump],
+                aim: Vec2::X,
+                ..default()
+            }],
+        };
+        let pretty = ron::ser::PrettyConfig::new().depth_limit(2);
+        let serialized = ron::ser::to_string_pretty(&recording, pretty).unwrap();
+        let deserialized: Recording = ron::from_str(&serialized).unwrap();
+
+        assert_eq!(deserialized.level_iid, recording.level_iid);
+        assert_eq!(deserialized.start_position, recording.start_position);
+        assert_eq!(deserialized.ticks[0].pressed, recording.ticks[0].pressed);
+        assert_eq!(deserialized.ticks[0].just_pressed, [InputAction::Jump]);
+        assert!(deserialized.ticks[0].scroll.is_empty());
+        assert_eq!(deserialized.tuning, recording.tuning);
+        assert!(deserialized.ticks[0].tuning.is_none());
+    }
+
+    #[test]
+    fn ticks_restore_just_pressed_and_released() {
+        let tick = RecordedTick {
+            pressed: vec![InputAction::MoveLeft, InputAction::Jump],
+            just_pressed: vec![InputAction::Jump, InputAction::Shoot],
+            just_released: vec![InputAction::Shoot, InputAction::Dash],
+            ..default()
+        };
+        let mut actions = ActionState::default();
+        actions.press(InputAction::Pause);
+        tick.apply(&mut actions);
+
+        assert!(actions.pressed(InputAction::MoveLeft));
+        assert!(!actions.just_pressed(InputAction::MoveLeft));
+        assert!(actions.just_pressed(InputAction::Jump));
+        // pressed and released between two ticks
+        assert!(!actions.pressed(InputAction::Shoot));
+        assert!(actions.just_pressed(InputAction::Shoot));
+        assert!(actions.just_released(InputAction::Shoot));
+        assert!(actions.just_released(InputAction::Dash));
+        assert!(!actions.pressed(InputAction::Pause));
+        assert!(!actions.just_released(InputAction::Pause));
+    }
+
+    #[test]
+    fn replay_forces_recorded_beam_aim_and_tuning() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let faster = MovementTuning {
+            max_h_vel: 3.0,
+            ..default()
+        };
+        let mut world = World::new();
+        world.init_resource::<ActionState>();
+        world.init_resource::<TickInput>();
+        world.init_resource::<AimDirection>();
+        world.init_resource::<BeamAim>();
+        world.insert_resource(MovementTuning {
+            gravity: 1.0,
+            ..default()
+        });
+        world.insert_resource(Replay {
+            recording: Recording {
+                level_iid: "level".to_owned(),
+                start_position: Vec2::ZERO,
+                seed: 0,
+                wind_time: 0.0,
+                tuning: MovementTuning::default(),
+                ticks: vec![
+                    RecordedTick {
+                        aim: Vec2::X,
+                        beam_aim: Vec2::Y,
+                        ..default()
+                    },
+                    RecordedTick {
+                        tuning: Some(faster.clone()),
+                        ..default()
+                    },
+                ],
+            },
+            tick: Some(0),
+            started: true,
+            frame: RecordedTick::default(),
+        });
+
+        world.run_system_once(feed_replay_input).unwrap();
+        // the aim assist would have turned the aim, and the replay has to follow it
+        assert_eq!(world.resource::<AimDirection>().0, Vec2::X);
+        assert_eq!(world.resource::<BeamAim>().0, Vec2::Y);
+        // the tuning of the start is applied by start_replay, not by the ticks
+        assert_eq!(world.resource::<MovementTuning>().gravity, 1.0);
+
+        world.run_system_once(feed_replay_input).unwrap();
+        assert_eq!(*world.resource::<MovementTuning>(), faster);
+    }
+
+    #[derive(Resource, Default)]
+    struct JumpCount {
+        ticks: usize,
+        frames: usize,
+    }
+
+    #[test]
+    fn replay_handles_every_tick_of_a_frame() {
+        use bevy::{
+            input::common_conditions::input_just_pressed, time::TimeUpdateStrategy, utils::HashSet,
+        };
+
+        use crate::input::action::tick_just_pressed;
+
+        const TICKS_PER_FRAME: u32 = 3;
+
+        // tap jump on some ticks, including several ticks of the same frame
+        let jump_ticks = [0, 1, 2, 4, 9];
+        let ticks = (0..12)
+            .map(|tick| RecordedTick {
+                just_pressed: if jump_ticks.contains(&tick) {
+                    vec![InputAction::Jump]
+                } else {
+                    Vec::new()
+                },
+                just_released: if jump_ticks.contains(&tick) {
+                    vec![InputAction::Jump]
+                } else {
+                    Vec::new()
+                },
+                ..default()
+            })
+            .collect::<Vec<_>>();
+        let frames_with_jumps = jump_ticks
+            .iter()
+            .map(|tick| tick / TICKS_PER_FRAME as usize)
+            .collect::<HashSet<_>>()
+            .len();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(
+                Time::<Fixed>::default().timestep() * TICKS_PER_FRAME,
+            ))
+            .init_resource::<ActionState>()
+            .init_resource::<TickInput>()
+            .init_resource::<AimDirection>()
+            .init_resource::<BeamAim>()
+            .init_resource::<MovementTuning>()
+            .init_resource::<JumpCount>()
+            .insert_resource(Replay {
+                recording: Recording {
+                    level_iid: "level".to_owned(),
+                    start_position: Vec2::ZERO,
+                    seed: 0,
+                    wind_time: 0.0,
+                    tuning: MovementTuning::default(),
+                    ticks,
+                },
+                tick: Some(0),
+                started: true,
+                frame: RecordedTick::default(),
+            })
+            .add_systems(FixedPreUpdate, feed_replay_input.run_if(replay_running))
+            .add_systems(
+                RunFixedMainLoop,
+                update_replay_action_state
+                    .in_set(RunFixedMainLoopSystem::AfterFixedMainLoop)
+                    .run_if(replay_running),
+            )
+            .add_systems(
+                FixedUpdate,
+                (|mut count: ResMut<JumpCount>| count.ticks += 1)
+                    .run_if(tick_just_pressed(InputAction::Jump)),
+            )
+            .add_systems(
+                Update,
+                (|mut count: ResMut<JumpCount>| count.frames += 1)
+                    .run_if(input_just_pressed(InputAction::Jump)),
+            );
+
+        // the first frame only starts the clock
+        for _ in 0..6 {
+            app.update();
+        }
+
+        assert!(!app.world().contains_resource::<Replay>());
+        let count = app.world().resource::<JumpCount>();
+        assert_eq!(count.ticks, jump_ticks.len());
+        assert_eq!(count.frames, frames_with_jumps);
+    }
+}
@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_tilemap::tiles::TileTextureIndex;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    lighting::LineLight2d,
+    player::PlayerHurtMarker,
+    shared::{ResetLevel, LYRA_RESPAWN_EPSILON},
+};
+
+use super::{
+    crystal::{reset_crystals, toggle_crystal, toggle_crystal_group, Crystal, CrystalGroup},
+    entity::FixedEntityBundle,
+    platform::{reset_platforms, MovingPlatform},
+    sensor::{reset_light_sensors, LightSensor},
+    CurrentLevel, LevelSystems,
+};
+
+/// The color of the light of a [`Checkpoint`] that hasn't been touched yet.
+const CHECKPOINT_INACTIVE_LIGHT: Vec4 = Vec4::new(0.6, 0.6, 0.6, 0.3);
+/// The color of the light of the [`ActiveCheckpoint`].
+const CHECKPOINT_ACTIVE_LIGHT: Vec4 = Vec4::new(1.0, 0.9, 0.6, 1.0);
+
+/// [`Plugin`] for mid-level checkpoints. Touching a [`Checkpoint`] makes the player respawn there
+/// instead of at the level's [`StartFlag`](super::start_flag::StartFlag), optionally restoring the
+/// state of the level at the time it was touched.
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveCheckpoint>()
+            .register_ldtk_entity::<CheckpointBundle>("Checkpoint")
+            .add_systems(
+                FixedUpdate,
+                activate_checkpoint.in_set(LevelSystems::Simulation),
+            )
+            .add_systems(
+                Update,
+                (
+                    clear_checkpoint_on_level_switch,
+                    restore_checkpoint_snapshot
+                        .after(reset_crystals)
+                        .after(reset_light_sensors),
+                )
+                    .in_set(LevelSystems::Reset),
+            )
+            .add_systems(
+                FixedUpdate,
+                restore_checkpoint_platforms
+                    .after(reset_platforms)
+                    .run_if(on_event::<ResetLevel>),
+            );
+    }
+}
+
+/// [`Component`] for checkpoints placed in the middle of a level.
+#[derive(Component, Debug)]
+pub struct Checkpoint {
+    /// Whether touching the checkpoint should also save the state of crystals, platforms and
+    /// sensors, so that they are restored when the player dies.
+    pub save_state: bool,
+}
+
+impl From<&EntityInstance> for Checkpoint {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let save_state = match entity_instance.get_bool_field("save_state") {
+            Ok(save_state) => *save_state,
+            Err(_) => false,
+        };
+        Checkpoint { save_state }
+    }
+}
+
+/// [`Bundle`] spawned in by Ldtk corresponding to checkpoints.
+#[derive(Bundle, LdtkEntity)]
+pub struct CheckpointBundle {
+    #[from_entity_instance]
+    checkpoint: Checkpoint,
+    #[from_entity_instance]
+    physics: FixedEntityBundle,
+    #[from_entity_instance]
+    instance: EntityInstance,
+    #[with(checkpoint_light)]
+    light: LineLight2d,
+    #[default]
+    sensor: Sensor,
+}
+
+pub fn checkpoint_light(_: &EntityInstance) -> LineLight2d {
+    LineLight2d::point(CHECKPOINT_INACTIVE_LIGHT, 30.0, 0.008)
+}
+
+/// The state of the level saved when touching a [`Checkpoint`] with
+/// [`save_state`](Checkpoint::save_state) enabled.
+#[derive(Default, Debug)]
+pub struct CheckpointSnapshot {
+    crystals: Vec<(Entity, bool)>,
+    crystal_groups: Vec<(Entity, bool)>,
+    sensors: Vec<(Entity, f32, bool)>,
+    platforms: Vec<(Entity, MovingPlatform, Transform)>,
+}
+
+/// Information about the last [`Checkpoint`] the player touched.
+#[derive(Debug)]
+pub struct CheckpointSave {
+    pub entity: Entity,
+    pub level_iid: LevelIid,
+    /// The position the player respawns at.
+    pub position: Vec2,
+    pub snapshot: Option<CheckpointSnapshot>,
+}
+
+/// [`Resource`] that holds the last [`Checkpoint`] touched in the current level, if any.
+#[derive(Resource, Default, Debug)]
+pub struct ActiveCheckpoint(pub Option<CheckpointSave>);
+
+impl ActiveCheckpoint {
+    /// The position the player should respawn at in the given level, if they touched a
+    /// checkpoint in it.
+    pub fn respawn_position(&self, level_iid: &LevelIid) -> Option<Vec2> {
+        self.0
+            .as_ref()
+            .filter(|save| save.level_iid == *level_iid)
+            .map(|save| save.position)
+    }
+}
+
+/// [`System`] that activates [`Checkpoint`]s when the player touches them.
+#[allow(clippy::too_many_arguments)]
+pub fn activate_checkpoint(
+    mut commands: Commands,
+    rapier_context: Query<&RapierContext>,
+    q_player: Query<Entity, With<PlayerHurtMarker>>,
+    mut q_checkpoints: Query<(Entity, &Checkpoint, &EntityInstance, &mut LineLight2d)>,
+    q_crystals: Query<(Entity, &Crystal)>,
+    q_crystal_groups: Query<(Entity, &CrystalGroup)>,
+    q_sensors: Query<(Entity, &LightSensor)>,
+    q_platforms: Query<(Entity, &MovingPlatform, &Transform)>,
+    current_level: Res<CurrentLevel>,
+    mut active_checkpoint: ResMut<ActiveCheckpoint>,
+    asset_server: Res<AssetServer>,
+) {
+    let Ok(rapier_context) = rapier_context.get_single() else {
+        return;
+    };
+    let Ok(player_entity) = q_player.get_single() else {
+        return;
+    };
+
+    let active_entity = active_checkpoint.0.as_ref().map(|save| save.entity);
+    let touched = q_checkpoints.iter().find(|(entity, ..)| {
+        Some(*entity) != active_entity
+            && rapier_context.intersection_pair(player_entity, *entity) == Some(true)
+    });
+    let Some((entity, checkpoint, instance, _)) = touched else {
+        return;
+    };
+    let (Some(x), Some(y)) = (instance.world_x, instance.world_y) else {
+        error!("Checkpoint entity has no coordinates! (This is probably because your LDTK world is not in free layout mode.)");
+        return;
+    };
+
+    let snapshot = checkpoint.save_state.then(|| CheckpointSnapshot {
+        crystals: q_crystals
+            .iter()
+            .map(|(entity, crystal)| (entity, crystal.active))
+            .collect(),
+        crystal_groups: q_crystal_groups
+            .iter()
+            .map(|(entity, group)| (entity, group.representative.active))
+            .collect(),
+        sensors: q_sensors
+            .iter()
+            .map(|(entity, sensor)| (entity, sensor.meter, sensor.is_active))
+            .collect(),
+        platforms: q_platforms
+            .iter()
+            .map(|(entity, platform, transform)| (entity, platform.clone(), *transform))
+            .collect(),
+    });
+
+    active_checkpoint.0 = Some(CheckpointSave {
+        entity,
+        level_iid: current_level.level_iid.clone(),
+        position: Vec2::new(x as f32, -y as f32 + LYRA_RESPAWN_EPSILON),
+        snapshot,
+    });
+
+    for (checkpoint_entity, _, _, mut light) in q_checkpoints.iter_mut() {
+        light.color = if checkpoint_entity == entity {
+            CHECKPOINT_ACTIVE_LIGHT
+        } else {
+            CHECKPOINT_INACTIVE_LIGHT
+        };
+    }
+    commands.entity(entity).with_child((
+        AudioPlayer::new(asset_server.load("sfx/button.wav")),
+        PlaybackSettings::DESPAWN,
+    ));
+}
+
+/// [`System`] that forgets the [`ActiveCheckpoint`] when the player leaves the level.
+pub fn clear_checkpoint_on_level_switch(
+    mut ev_reset_level: EventReader<ResetLevel>,
+    mut active_checkpoint: ResMut<ActiveCheckpoint>,
+    mut q_checkpoints: Query<&mut LineLight2d, With<Checkpoint>>,
+) {
+    if !ev_reset_level.read().any(|ev| *ev == ResetLevel::Switching) {
+        return;
+    }
+    active_checkpoint.0 = None;
+    for mut light in q_checkpoints.iter_mut() {
+        light.color = CHECKPOINT_INACTIVE_LIGHT;
+    }
+}
+
+/// [`System`] that restores the crystals and sensors saved in the [`ActiveCheckpoint`] after they
+/// have been reset by a [`ResetLevel::Respawn`].
+pub fn restore_checkpoint_snapshot(
+    mut commands: Commands,
+    mut ev_reset_level: EventReader<ResetLevel>,
+    active_checkpoint: Res<ActiveCheckpoint>,
+    current_level: Res<CurrentLevel>,
+    mut q_crystals: Query<(&mut Crystal, &mut TileTextureIndex)>,
+    mut q_crystal_groups: Query<&mut CrystalGroup>,
+    mut q_sensors: Query<&mut LightSensor>,
+) {
+    if !ev_reset_level.read().any(|ev| *ev == ResetLevel::Respawn) {
+        return;
+    }
+    let Some(save) = &active_checkpoint.0 else {
+        return;
+    };
+    let Some(snapshot) = &save.snapshot else {
+        return;
+    };
+    if save.level_iid != current_level.level_iid {
+        return;
+    }
+
+    for &(entity, active) in snapshot.crystals.iter() {
+        let Ok((mut crystal, mut index)) = q_crystals.get_mut(entity) else {
+            continue;
+        };
+        if crystal.active != active {
+            toggle_crystal(&mut crystal, &mut index);
+        }
+    }
+    for &(entity, active) in snapshot.crystal_groups.iter() {
+        let Ok(mut crystal_group) = q_crystal_groups.get_mut(entity) else {
+            continue;
+        };
+        if crystal_group.representative.active != active {
+            toggle_crystal_group(&mut commands, entity, &mut crystal_group);
+        }
+    }
+    for &(entity, meter, is_active) in snapshot.sensors.iter() {
+        let Ok(mut sensor) = q_sensors.get_mut(entity) else {
+            continue;
+        };
+        sensor.meter = meter;
+        sensor.is_active = is_active;
+    }
+}
+
+/// [`System`] that restores the [`MovingPlatform`]s saved in the [`ActiveCheckpoint`] after they
+/// have been reset by [`reset_platforms`].
+pub fn restore_checkpoint_platforms(
+    mut ev_reset_level: EventReader<ResetLevel>,
+    active_checkpoint: Res<ActiveCheckpoint>,
+    current_level: Res<CurrentLevel>,
+    mut q_platforms: Query<(&mut MovingPlatform, &mut Transform)>,
+) {
+    if !ev_reset_level.read().any(|ev| *ev == ResetLevel::Respawn) {
+        return;
+    }
+    let Some(save) = &active_checkpoint.0 else {
+        return;
+    };
+    let Some(snapshot) = &save.snapshot else {
+        return;
+    };
+    if save.level_iid != current_level.level_iid {
+        return;
+    }
+
+    for (entity, saved_platform, saved_transform) in snapshot.platforms.iter() {
+        let Ok((mut platform, mut transform)) = q_platforms.get_mut(*entity) else {
+            continue;
+        };
+        *platform = saved_platform.clone();
+        *transform = *saved_transform;
+    }
+}
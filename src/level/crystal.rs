@@ -8,7 +8,7 @@ use bevy_rapier2d::prelude::*;
 use crate::{lighting::Occluder2d, shared::GroupLabel};
 
 use super::{
-    entity::HurtMarker,
+    entity::{HurtMarker, LdtkEntityError},
     merge_tile::{spawn_merged_tiles, MergedTile},
     sensor::update_light_sensors,
     CurrentLevel, LevelSystems,
@@ -46,7 +46,8 @@ impl Plugin for CrystalPlugin {
                     .in_set(LevelSystems::Simulation)
                     .after(update_light_sensors),
             )
-            .add_systems(Update, reset_crystals.in_set(LevelSystems::Reset));
+            .add_systems(Update, reset_crystals.in_set(LevelSystems::Reset))
+            .add_systems(PreUpdate, clear_crystal_cache.in_set(LevelSystems::Reload));
 
         for i in 3..=10 {
             app.register_ldtk_int_cell_for_layer::<CrystalBundle>("Terrain", i);
@@ -78,14 +79,18 @@ impl CrystalColor {
     }
 }
 
-impl From<&String> for CrystalColor {
-    fn from(value: &String) -> Self {
+impl TryFrom<&String> for CrystalColor {
+    type Error = LdtkEntityError;
+
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
         match value.as_str() {
-            "Pink" => CrystalColor::Pink,
-            "Red" => CrystalColor::Red,
-            "White" => CrystalColor::White,
-            "Blue" => CrystalColor::Blue,
-            _ => panic!("String does not represent a CrystalColor"),
+            "Pink" => Ok(CrystalColor::Pink),
+            "Red" => Ok(CrystalColor::Red),
+            "White" => Ok(CrystalColor::White),
+            "Blue" => Ok(CrystalColor::Blue),
+            _ => Err(LdtkEntityError::new(format!(
+                "{value} does not represent a CrystalColor"
+            ))),
         }
     }
 }
@@ -206,6 +211,10 @@ fn invalidate_crystal_cache(
     }
 }
 
+fn clear_crystal_cache(mut crystal_cache: ResMut<CrystalCache>) {
+    *crystal_cache = CrystalCache::default();
+}
+
 fn init_crystal_cache_groups(
     q_crystal_groups: Query<(Entity, &Parent, &CrystalGroup), Added<CrystalGroup>>,
     q_level_iid: Query<&LevelIid>,
@@ -291,7 +300,7 @@ fn init_crystal_cache_tiles(
 /// Function to determine whether or not a cell value represents an Active Crystal. Does not use
 /// the modulo operator as future crystal cell values need not necessarily follow the same pattern
 /// in the future.
-fn is_crystal_active(cell_value: IntGridCell) -> bool {
+pub fn is_crystal_active(cell_value: IntGridCell) -> bool {
     match cell_value.value {
         3 | 5 | 7 | 9 => true,
         4 | 6 | 8 | 10 => false,
@@ -300,7 +309,7 @@ fn is_crystal_active(cell_value: IntGridCell) -> bool {
 }
 
 /// Function to determine the base color of the crystal.
-fn crystal_color(cell_value: IntGridCell) -> CrystalColor {
+pub fn crystal_color(cell_value: IntGridCell) -> CrystalColor {
     match cell_value.value {
         3 | 4 => CrystalColor::Pink,
         5 | 6 => CrystalColor::Red,
@@ -329,7 +338,7 @@ impl From<IntGridCell> for Crystal {
 /// The horizontal offset between active crystals and inactive crystals in the crystal tilemap
 const CRYSTAL_INDEX_OFFSET: u32 = 5;
 
-fn toggle_crystal_group(
+pub(super) fn toggle_crystal_group(
     commands: &mut Commands,
     crystal_group_entity: Entity,
     crystal_group: &mut CrystalGroup,
@@ -349,7 +358,7 @@ fn toggle_crystal_group(
     }
 }
 
-fn toggle_crystal(crystal: &mut Crystal, crystal_index: &mut TileTextureIndex) {
+pub(super) fn toggle_crystal(crystal: &mut Crystal, crystal_index: &mut TileTextureIndex) {
     if !crystal.active {
         crystal.active = true;
         crystal_index.0 -= CRYSTAL_INDEX_OFFSET;
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
@@ -29,6 +31,109 @@ pub struct FixedEntityBundle {
     pub collision_groups: CollisionGroups,
 }
 
+/// The color of the sprite shown in place of an entity that failed to convert.
+const ERROR_MARKER_COLOR: Color = Color::srgb(1.0, 0.0, 1.0);
+
+/// Error returned when an [`EntityInstance`] can't be converted into a [`Component`], usually
+/// because one of its fields is missing or has the wrong type.
+#[derive(Debug)]
+pub struct LdtkEntityError(String);
+
+impl LdtkEntityError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl fmt::Display for LdtkEntityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<E: std::error::Error> From<E> for LdtkEntityError {
+    fn from(err: E) -> Self {
+        Self(err.to_string())
+    }
+}
+
+/// [`Component`] used with `#[from_entity_instance]` in place of a `T` whose conversion from an
+/// [`EntityInstance`] can fail. [`resolve_fallible`] then inserts the `T`, or turns the entity into
+/// an [`LdtkEntityErrorMarker`] so that a broken entity doesn't take down the whole level.
+#[derive(Component)]
+pub struct Fallible<T> {
+    result: Option<Result<T, LdtkEntityError>>,
+    /// The identifier and iid of the entity, for the error message.
+    name: String,
+    size: Vec2,
+}
+
+impl<T> Default for Fallible<T> {
+    fn default() -> Self {
+        Self {
+            result: None,
+            name: String::new(),
+            size: Vec2::ZERO,
+        }
+    }
+}
+
+impl<T> From<&EntityInstance> for Fallible<T>
+where
+    T: for<'a> TryFrom<&'a EntityInstance, Error = LdtkEntityError>,
+{
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            result: Some(T::try_from(entity_instance)),
+            name: format!("{} {}", entity_instance.identifier, entity_instance.iid),
+            size: Vec2::new(entity_instance.width as f32, entity_instance.height as f32),
+        }
+    }
+}
+
+/// [`Component`] for entities that failed to convert from their [`EntityInstance`], which are
+/// shown as a magenta box instead.
+#[derive(Component)]
+pub struct LdtkEntityErrorMarker;
+
+/// [`System`] that replaces every [`Fallible<T>`] with its `T`. Entities that failed to convert
+/// lose everything but their place in the level, and get an error marker sprite. Should run in
+/// [`LevelSystems::Processing`](super::LevelSystems::Processing) before anything that looks for an
+/// [`Added<T>`].
+pub fn resolve_fallible<T: Component>(
+    mut commands: Commands,
+    mut q_fallible: Query<(Entity, &mut Fallible<T>), Added<Fallible<T>>>,
+) {
+    for (entity, mut fallible) in q_fallible.iter_mut() {
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<Fallible<T>>();
+        match fallible.result.take() {
+            Some(Ok(component)) => {
+                entity_commands.insert(component);
+            }
+            Some(Err(err)) => {
+                error!("Could not spawn {}: {err}", fallible.name);
+                entity_commands
+                    .despawn_descendants()
+                    .retain::<(
+                        Transform,
+                        GlobalTransform,
+                        Visibility,
+                        InheritedVisibility,
+                        ViewVisibility,
+                        Parent,
+                        EntityIid,
+                    )>()
+                    .insert((
+                        LdtkEntityErrorMarker,
+                        Sprite::from_color(ERROR_MARKER_COLOR, fallible.size),
+                    ));
+            }
+            None => {}
+        }
+    }
+}
+
 impl From<&EntityInstance> for FixedEntityBundle {
     fn from(entity_instance: &EntityInstance) -> Self {
         match entity_instance.identifier.as_ref() {
@@ -48,7 +153,21 @@ impl From<&EntityInstance> for FixedEntityBundle {
                     GroupLabel::PLAYER_SENSOR,
                 ),
             },
-            _ => unreachable!(),
+            "Checkpoint" => FixedEntityBundle {
+                collider: Collider::cuboid(
+                    (entity_instance.width / 2) as f32,
+                    (entity_instance.height / 2) as f32,
+                ),
+                rigid_body: RigidBody::Fixed,
+                collision_groups: CollisionGroups::new(GroupLabel::ALL, GroupLabel::PLAYER_SENSOR),
+            },
+            _ => {
+                error!(
+                    "{} {} has no physics",
+                    entity_instance.identifier, entity_instance.iid
+                );
+                FixedEntityBundle::default()
+            }
         }
     }
 }
@@ -82,3 +201,77 @@ impl From<IntGridCell> for FixedEntityBundle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs_ldtk::ldtk::{FieldInstance, FieldValue};
+
+    use super::*;
+
+    #[derive(Component)]
+    struct Strength(f32);
+
+    impl TryFrom<&EntityInstance> for Strength {
+        type Error = LdtkEntityError;
+
+        fn try_from(entity_instance: &EntityInstance) -> Result<Self, Self::Error> {
+            Ok(Strength(*entity_instance.get_float_field("strength")?))
+        }
+    }
+
+    #[test]
+    fn entity_gets_converted_component() {
+        let mut app = App::new();
+        app.add_systems(Update, resolve_fallible::<Strength>);
+
+        let entity_instance = EntityInstance {
+            identifier: "Wind".to_string(),
+            field_instances: vec![FieldInstance {
+                identifier: "strength".to_string(),
+                tile: None,
+                field_instance_type: "Float".to_string(),
+                value: FieldValue::Float(Some(2.0)),
+                def_uid: 0,
+                real_editor_values: Vec::new(),
+            }],
+            ..default()
+        };
+        let entity = app
+            .world_mut()
+            .spawn(Fallible::<Strength>::from(&entity_instance))
+            .id();
+        app.update();
+
+        let entity = app.world().entity(entity);
+        assert_eq!(
+            entity.get::<Strength>().map(|strength| strength.0),
+            Some(2.0)
+        );
+        assert!(!entity.contains::<Fallible<Strength>>());
+        assert!(!entity.contains::<LdtkEntityErrorMarker>());
+    }
+
+    #[test]
+    fn broken_entity_becomes_error_marker() {
+        let mut app = App::new();
+        app.add_systems(Update, resolve_fallible::<Strength>);
+
+        // no `strength` field
+        let entity_instance = EntityInstance {
+            identifier: "Broken".to_string(),
+            width: 16,
+            height: 8,
+            ..default()
+        };
+        let entity = app
+            .world_mut()
+            .spawn(Fallible::<Strength>::from(&entity_instance))
+            .id();
+        app.update();
+
+        let entity = app.world().entity(entity);
+        assert!(entity.contains::<LdtkEntityErrorMarker>());
+        assert!(!entity.contains::<Fallible<Strength>>());
+        assert!(!entity.contains::<Strength>());
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{
+    assets::LdtkExternalLevel,
+    prelude::*,
+    systems::{process_ldtk_assets, process_ldtk_levels},
+    LevelIid,
+};
+
+use crate::{
+    camera::{camera_position_from_level, CameraControlType, CameraMoveEvent},
+    player::PlayerMarker,
+};
+
+use super::{
+    get_loaded_level, level_box_from_level, setup::lyra_level_index,
+    start_flag::start_flag_position, CurrentLevel, LevelSystems,
+};
+
+/// [`Plugin`] that respawns the level when the LDtk project changes on disk, so that levels can be
+/// edited while the game is running. Caches of the spawned levels are cleared by systems in
+/// [`LevelSystems::Reload`], and the player is put back where they were.
+pub struct LevelHotReloadPlugin;
+
+impl Plugin for LevelHotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LevelReloadEvent>()
+            .add_systems(PreUpdate, detect_ldtk_reload.before(process_ldtk_assets))
+            .add_systems(
+                PreUpdate,
+                restore_player_after_reload
+                    .in_set(LevelSystems::Processing)
+                    .run_if(resource_exists::<PendingReload>),
+            )
+            .configure_sets(
+                PreUpdate,
+                LevelSystems::Reload
+                    .run_if(on_event::<LevelReloadEvent>)
+                    .after(detect_ldtk_reload)
+                    .before(process_ldtk_levels),
+            );
+    }
+}
+
+/// [`Event`] sent when the LDtk project is about to be respawned, because it changed on disk or
+/// another [`LevelPack`](crate::level_pack::LevelPack) was picked.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LevelReloadEvent;
+
+/// [`Resource`] inserted while the LDtk project is being respawned after a reload.
+#[derive(Resource, Debug)]
+struct PendingReload {
+    /// The player before the reload, which is despawned along with the rest of the world.
+    old_player: Option<Entity>,
+    player_position: Option<Vec2>,
+}
+
+/// [`System`] that notices when the LDtk project is modified, before `bevy_ecs_ldtk` respawns the
+/// world, and remembers where the player was.
+#[allow(clippy::too_many_arguments)]
+fn detect_ldtk_reload(
+    mut commands: Commands,
+    mut ev_asset: EventReader<AssetEvent<LdtkProject>>,
+    q_ldtk: Query<&LdtkProjectHandle>,
+    q_player: Query<(Entity, &Transform), With<PlayerMarker>>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    external_levels: Res<Assets<LdtkExternalLevel>>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut ev_reload: EventWriter<LevelReloadEvent>,
+) {
+    let Ok(ldtk_handle) = q_ldtk.get_single() else {
+        return;
+    };
+    if !ev_asset.read().any(|ev| ev.is_modified(ldtk_handle)) {
+        return;
+    }
+    info!("LDtk project changed, reloading the level");
+
+    let player = q_player.get_single().ok();
+    commands.insert_resource(PendingReload {
+        old_player: player.map(|(entity, _)| entity),
+        player_position: player.map(|(_, transform)| transform.translation.xy()),
+    });
+    // worldly entities such as the player are respawned along with the level they are placed in
+    if let Some(index) = ldtk_assets
+        .get(ldtk_handle)
+        .and_then(|ldtk_project| lyra_level_index(ldtk_project, &external_levels))
+    {
+        *level_selection = LevelSelection::index(index);
+    }
+    ev_reload.send(LevelReloadEvent);
+}
+
+/// [`System`] that puts the player back where they were once they have been respawned, or at the
+/// nearest `Start` flag if the level they were in is gone. Runs before [`FixedUpdate`] so that
+/// [`switch_level`](super::switch_level) never sees the player at their spawn point.
+#[allow(clippy::too_many_arguments)]
+fn restore_player_after_reload(
+    mut commands: Commands,
+    pending: Res<PendingReload>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    external_levels: Res<Assets<LdtkExternalLevel>>,
+    q_ldtk: Query<&LdtkProjectHandle>,
+    mut q_player: Query<(Entity, &mut Transform), With<PlayerMarker>>,
+    mut ev_move_camera: EventWriter<CameraMoveEvent>,
+    mut current_level: ResMut<CurrentLevel>,
+) {
+    let Ok(ldtk_handle) = q_ldtk.get_single() else {
+        return;
+    };
+    let Some(ldtk_project) = ldtk_assets.get(ldtk_handle) else {
+        return;
+    };
+    let ldtk_levels = &ldtk_project.json_data().levels;
+    let Ok((player, mut player_transform)) = q_player.get_single_mut() else {
+        return;
+    };
+    if pending.old_player == Some(player) {
+        return;
+    }
+    commands.remove_resource::<PendingReload>();
+
+    let spawn_position = player_transform.translation.xy();
+    let old_position = pending.player_position.unwrap_or(spawn_position);
+    let position = if ldtk_levels
+        .iter()
+        .any(|level| level_box_from_level(level).contains(old_position))
+    {
+        old_position
+    } else {
+        ldtk_levels
+            .iter()
+            .filter_map(|level| get_loaded_level(ldtk_project, &external_levels, &level.iid))
+            .filter_map(start_flag_position)
+            .min_by(|a, b| {
+                a.distance_squared(old_position)
+                    .total_cmp(&b.distance_squared(old_position))
+            })
+            .unwrap_or(spawn_position)
+    };
+    player_transform.translation.x = position.x;
+    player_transform.translation.y = position.y;
+
+    if let Some(level) = ldtk_levels
+        .iter()
+        .find(|level| level_box_from_level(level).contains(position))
+    {
+        ev_move_camera.send(CameraMoveEvent {
+            to: camera_position_from_level(level_box_from_level(level), position),
+            variant: CameraControlType::Instant,
+        });
+    }
+    // respawn the level the player is in without the camera transition, see handle_level_selection
+    current_level.level_iid = LevelIid::new("");
+}
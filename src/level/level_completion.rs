@@ -13,12 +13,22 @@ impl Plugin for LevelCompletionPlugin {
         app.register_ldtk_entity::<CompletionMarkerBundle>("StartMarker")
             .register_ldtk_entity::<CompletionMarkerBundle>("EndMarker")
             .insert_resource(InProgressLevel(LevelIid::default()))
+            .add_event::<LevelCompletionEvent>()
             .add_systems(Update, handle_start_end_markers);
     }
 }
 
+/// [`Event`] sent when the player touches the `StartMarker` or `EndMarker` of a level. Starting is
+/// sent every frame the player touches the `StartMarker`, so a run begins once they leave it.
+/// Completing is only sent once per run.
+#[derive(Event, Debug, Clone)]
+pub enum LevelCompletionEvent {
+    Started(LevelIid),
+    Completed(LevelIid),
+}
+
 #[derive(Component)]
-enum CompletionMarkerType {
+pub enum CompletionMarkerType {
     StartMarker,
     EndMarker,
 }
@@ -32,7 +42,7 @@ struct CompletionMarkerBundle {
 }
 
 #[derive(Resource)]
-struct InProgressLevel(LevelIid);
+pub struct InProgressLevel(LevelIid);
 
 impl LdtkEntity for CompletionMarkerBundle {
     fn bundle_entity(
@@ -63,13 +73,14 @@ impl LdtkEntity for CompletionMarkerBundle {
     }
 }
 
-fn handle_start_end_markers(
+pub fn handle_start_end_markers(
     rapier_context: Query<&RapierContext>,
     q_player: Query<Entity, With<PlayerHurtMarker>>,
     q_completion_markers: Query<(Entity, &CompletionMarkerType), Without<PlayerHurtMarker>>,
     mut res_levels: ResMut<Levels>,
     res_current_level: Res<CurrentLevel>,
     mut res_in_progress_level: ResMut<InProgressLevel>,
+    mut ev_completion: EventWriter<LevelCompletionEvent>,
 ) {
     let (Ok(rapier_context), Ok(player_entity), completion_markers) = (
         rapier_context.get_single(),
@@ -85,6 +96,9 @@ fn handle_start_end_markers(
         match marker_type {
             CompletionMarkerType::StartMarker => {
                 res_in_progress_level.0 = res_current_level.level_iid.clone();
+                ev_completion.send(LevelCompletionEvent::Started(
+                    res_current_level.level_iid.clone(),
+                ));
             }
             CompletionMarkerType::EndMarker => {
                 let current = &res_current_level.level_iid;
@@ -102,6 +116,9 @@ fn handle_start_end_markers(
                         unlock_next = true;
                     }
                 }
+                ev_completion.send(LevelCompletionEvent::Completed(current.clone()));
+                // the run is over, so standing on the `EndMarker` shouldn't complete it again
+                res_in_progress_level.0 = LevelIid::default();
             }
         }
     }
@@ -1,10 +1,12 @@
 use bevy::prelude::*;
-use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::{assets::LdtkExternalLevel, prelude::*};
 use std::{
     collections::{HashMap, HashSet},
     hash::Hash,
 };
 
+use super::get_loaded_level;
+
 pub trait MergedTile {
     /// The comparison data used to compute if two tiles are mergeable or not
     type CompareData: PartialEq + Eq + Hash;
@@ -31,6 +33,7 @@ pub fn spawn_merged_tiles<TILE>(
     level_query: Query<(Entity, &LevelIid)>,
     ldtk_projects: Query<&LdtkProjectHandle>,
     ldtk_project_assets: Res<Assets<LdtkProject>>,
+    external_levels: Res<Assets<LdtkExternalLevel>>,
 ) where
     TILE: MergedTile + Component,
 {
@@ -75,9 +78,7 @@ pub fn spawn_merged_tiles<TILE>(
             return;
         };
 
-        let level = ldtk_project
-            .as_standalone()
-            .get_loaded_level_by_iid(&level_iid.to_string())
+        let level = get_loaded_level(ldtk_project, &external_levels, level_iid.as_str())
             .expect("Spawned level should exist in LDtk project");
 
         let LayerInstance {
@@ -0,0 +1,157 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_ecs_ldtk::{ldtk::Level, prelude::*, LevelIid};
+
+use crate::sound::{BgmTrack, ChangeBgmEvent};
+
+use super::{get_ldtk_level_data, CurrentLevel, LevelSystems};
+
+const CHAPTER_FIELD: &str = "Chapter";
+const CHAPTER_NAME_FIELD: &str = "ChapterName";
+const BGM_FIELD: &str = "Bgm";
+
+/// [`Plugin`] that reads the chapter and music of every level into the [`LevelMetadataCache`] once
+/// the LDtk project has loaded.
+pub struct LevelMetadataPlugin;
+
+impl Plugin for LevelMetadataPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelMetadataCache>().add_systems(
+            PreUpdate,
+            (
+                clear_level_metadata_cache.in_set(LevelSystems::Reload),
+                init_level_metadata_cache.after(LevelSystems::Reload),
+            ),
+        );
+    }
+}
+
+/// The chapter and music of a level, from its `Chapter`, `ChapterName` and `Bgm` level fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelMetadata {
+    pub chapter: Option<i32>,
+    pub chapter_name: Option<String>,
+    pub bgm: BgmTrack,
+}
+
+impl LevelMetadata {
+    fn from_level(level: &Level) -> Self {
+        let chapter = match level.get_maybe_int_field(CHAPTER_FIELD) {
+            Ok(chapter) => *chapter,
+            // levels made before the field was added
+            Err(_) => level
+                .get_string_field("LevelId")
+                .ok()
+                .and_then(|level_id| chapter_from_level_id(level_id)),
+        };
+        let chapter_name = level
+            .get_maybe_string_field(CHAPTER_NAME_FIELD)
+            .ok()
+            .cloned()
+            .flatten()
+            .filter(|chapter_name| !chapter_name.is_empty());
+        let bgm = match level.get_maybe_enum_field(BGM_FIELD) {
+            Ok(Some(bgm)) => BgmTrack::try_from(bgm).unwrap_or_else(|err| {
+                error!("Bgm of level {}: {err}", level.iid);
+                BgmTrack::None
+            }),
+            _ => chapter_bgm(chapter),
+        };
+        LevelMetadata {
+            chapter,
+            chapter_name,
+            bgm,
+        }
+    }
+
+    /// The title of the chapter shown in the level select and on title cards, e.g.
+    /// `Chapter 2: Name`.
+    pub fn chapter_title(&self) -> Option<String> {
+        let chapter = self.chapter?;
+        Some(match &self.chapter_name {
+            Some(chapter_name) => format!("Chapter {chapter}: {chapter_name}"),
+            None => format!("Chapter {chapter}"),
+        })
+    }
+}
+
+/// The chapter of a level from the digit its `LevelId` starts with, e.g. `2C` is in chapter 2.
+fn chapter_from_level_id(level_id: &str) -> Option<i32> {
+    let chapter = level_id.chars().next()?.to_digit(10)?;
+    Some(chapter as i32)
+}
+
+/// The music of levels without a `Bgm` field.
+fn chapter_bgm(chapter: Option<i32>) -> BgmTrack {
+    match chapter {
+        Some(1 | 2) => BgmTrack::MustntStop,
+        Some(3) => BgmTrack::Cutscene1Draft,
+        Some(4) => BgmTrack::LightInTheDark,
+        _ => BgmTrack::None,
+    }
+}
+
+/// [`Resource`] holding the [`LevelMetadata`] of every level, so that the LDtk fields are only read
+/// once.
+#[derive(Resource, Default, Debug)]
+pub struct LevelMetadataCache(HashMap<LevelIid, LevelMetadata>);
+
+impl LevelMetadataCache {
+    pub fn get(&self, level_iid: &LevelIid) -> Option<&LevelMetadata> {
+        self.0.get(level_iid)
+    }
+}
+
+fn init_level_metadata_cache(
+    mut level_metadata: ResMut<LevelMetadataCache>,
+    q_ldtk: Query<&LdtkProjectHandle>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+) {
+    if !level_metadata.0.is_empty() {
+        return;
+    }
+    let Ok(ldtk_handle) = q_ldtk.get_single() else {
+        return;
+    };
+    let Ok(ldtk_levels) = get_ldtk_level_data(ldtk_assets.into_inner(), ldtk_handle) else {
+        return;
+    };
+    level_metadata.0 = ldtk_levels
+        .iter()
+        .map(|level| {
+            (
+                LevelIid::new(level.iid.clone()),
+                LevelMetadata::from_level(level),
+            )
+        })
+        .collect();
+}
+
+/// [`System`] that clears the [`LevelMetadataCache`] when the LDtk project is reloaded, so that it
+/// is rebuilt from the new levels.
+fn clear_level_metadata_cache(mut level_metadata: ResMut<LevelMetadataCache>) {
+    level_metadata.0.clear();
+}
+
+pub fn set_bgm_from_current_level(
+    current_level: Res<CurrentLevel>,
+    level_metadata: Res<LevelMetadataCache>,
+    mut ev_change_bgm: EventWriter<ChangeBgmEvent>,
+) {
+    let bgm = level_metadata
+        .get(&current_level.level_iid)
+        .map_or(BgmTrack::None, |metadata| metadata.bgm);
+    ev_change_bgm.send(ChangeBgmEvent(bgm));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chapter_is_the_first_digit_of_the_level_id() {
+        assert_eq!(chapter_from_level_id("2C"), Some(2));
+        assert_eq!(chapter_from_level_id("4#"), Some(4));
+        assert_eq!(chapter_from_level_id(".End"), None);
+        assert_eq!(chapter_from_level_id(""), None);
+    }
+}
@@ -1,12 +1,21 @@
 use std::time::Duration;
 
 use bevy::{ecs::system::SystemId, prelude::*};
-use bevy_ecs_ldtk::{ldtk::Level, prelude::*, systems::process_ldtk_levels, LevelIid};
+use bevy_ecs_ldtk::{
+    assets::{LdtkExternalLevel, LdtkProjectData},
+    ldtk::{loaded_level::LoadedLevel, Level},
+    prelude::*,
+    systems::process_ldtk_levels,
+    LevelIid,
+};
+use checkpoint::CheckpointPlugin;
 use decoration::DecorationPlugin;
 use egg::EggPlugin;
 use enum_map::{enum_map, EnumMap};
+use hot_reload::LevelHotReloadPlugin;
 use level_completion::LevelCompletionPlugin;
 use merge_tile::spawn_merged_tiles;
+use metadata::{set_bgm_from_current_level, LevelMetadataCache, LevelMetadataPlugin};
 use mirror::MirrorPlugin;
 use semisolid::SemiSolidPlugin;
 use sensor::LightSensorPlugin;
@@ -20,7 +29,6 @@ use crate::{
     light::LightColor,
     player::{LdtkPlayerBundle, PlayerMarker},
     shared::{AnimationState, GameState, ResetLevel},
-    sound::{BgmTrack, ChangeBgmEvent},
 };
 use crystal::CrystalPlugin;
 use entity::SpikeBundle;
@@ -28,13 +36,18 @@ use platform::PlatformPlugin;
 use setup::LevelSetupPlugin;
 use start_flag::{init_start_marker, StartFlagBundle};
 use walls::{Wall, WallBundle};
+use water::WaterPlugin;
+use wind::WindPlugin;
 
+pub mod checkpoint;
 pub mod crystal;
 mod decoration;
 mod egg;
 pub mod entity;
-mod level_completion;
+pub mod hot_reload;
+pub mod level_completion;
 mod merge_tile;
+pub mod metadata;
 pub mod mirror;
 pub mod platform;
 mod semisolid;
@@ -43,6 +56,8 @@ mod setup;
 pub mod shard;
 pub mod start_flag;
 mod walls;
+pub mod water;
+pub mod wind;
 
 /// [`Plugin`] that handles everything related to the level.
 pub struct LevelManagementPlugin;
@@ -60,6 +75,11 @@ impl Plugin for LevelManagementPlugin {
             .add_plugins(EggPlugin)
             .add_plugins(LevelCompletionPlugin)
             .add_plugins(DecorationPlugin)
+            .add_plugins(WindPlugin)
+            .add_plugins(WaterPlugin)
+            .add_plugins(CheckpointPlugin)
+            .add_plugins(LevelHotReloadPlugin)
+            .add_plugins(LevelMetadataPlugin)
             .init_resource::<CurrentLevel>()
             .register_ldtk_entity::<LdtkPlayerBundle>("Lyra")
             .register_ldtk_entity::<StartFlagBundle>("Start")
@@ -73,7 +93,12 @@ impl Plugin for LevelManagementPlugin {
                 FixedUpdate,
                 (
                     switch_level,
-                    set_bgm_from_current_level.in_set(LevelSystems::Simulation),
+                    set_bgm_from_current_level
+                        .in_set(LevelSystems::Simulation)
+                        .run_if(
+                            resource_changed::<CurrentLevel>
+                                .or(resource_changed::<LevelMetadataCache>),
+                        ),
                 )
                     .chain()
                     .after(handle_level_selection),
@@ -113,6 +138,8 @@ pub struct CurrentLevel {
     pub level_iid: LevelIid,
     pub level_box: Rect,
     pub allowed_colors: EnumMap<LightColor, bool>,
+    /// Whether the player can dash in this level, from the `AllowDash` level field.
+    pub allow_dash: bool,
 }
 
 /// [`SystemSet`] used to distinguish different types of systems
@@ -124,6 +151,9 @@ pub enum LevelSystems {
     Processing,
     /// Systems used to clean up the level when the room switches or the player respawns
     Reset,
+    /// Systems used to clear caches of the spawned levels in [`PreUpdate`] when the LDtk project
+    /// is reloaded, see [`LevelReloadEvent`](hot_reload::LevelReloadEvent)
+    Reload,
 }
 
 pub fn get_ldtk_level_data<'ldtk>(
@@ -136,6 +166,23 @@ pub fn get_ldtk_level_data<'ldtk>(
     Ok(&ldtk_project.json_data().levels)
 }
 
+/// Gets a level along with its layers, which are in a file of their own if the project uses LDtk's
+/// "Separate level files" option. Returns [`None`] if the level doesn't exist or its file hasn't
+/// loaded yet.
+pub fn get_loaded_level<'ldtk>(
+    ldtk_project: &'ldtk LdtkProject,
+    external_levels: &'ldtk Assets<LdtkExternalLevel>,
+    level_iid: &str,
+) -> Option<LoadedLevel<'ldtk>> {
+    let level_iid = level_iid.to_owned();
+    match ldtk_project.data() {
+        LdtkProjectData::Standalone(project) => project.get_loaded_level_by_iid(&level_iid),
+        LdtkProjectData::Parent(project) => {
+            project.get_external_level_by_iid(external_levels, &level_iid)
+        }
+    }
+}
+
 pub fn level_box_from_level(level: &Level) -> Rect {
     Rect::new(
         level.world_x as f32,
@@ -196,20 +243,37 @@ pub fn switch_level(
                     ev_level_switch.send(ResetLevel::Switching);
                 }
 
-                let allowed_colors = level
-                    .iter_enums_field("AllowedColors")
-                    .expect("AllowedColors should be enum array level field.")
-                    .map(|color_str| color_str.into())
-                    .collect::<Vec<LightColor>>();
+                let allowed_colors = match level.iter_enums_field("AllowedColors") {
+                    Ok(colors) => colors
+                        .filter_map(|color_str| match LightColor::try_from(color_str) {
+                            Ok(color) => Some(color),
+                            Err(err) => {
+                                error!("AllowedColors of level {}: {err}", level.iid);
+                                None
+                            }
+                        })
+                        .collect::<Vec<LightColor>>(),
+                    Err(err) => {
+                        error!("Level {} allows no colors: {err}", level.iid);
+                        Vec::new()
+                    }
+                };
 
                 let allowed_colors_map = enum_map! {
                     val => allowed_colors.contains(&val),
                 };
 
+                // levels made before dashing was added don't have the field
+                let allow_dash = match level.get_bool_field("AllowDash") {
+                    Ok(allow_dash) => *allow_dash,
+                    Err(_) => false,
+                };
+
                 *current_level = CurrentLevel {
                     level_iid: LevelIid::new(level.iid.clone()),
                     level_box,
                     allowed_colors: allowed_colors_map,
+                    allow_dash,
                 };
                 *level_selection = LevelSelection::iid(current_level.level_iid.clone());
             }
@@ -233,39 +297,3 @@ pub fn on_finish_level_switch(
     next_game_state.set(GameState::Playing);
     ev_reset_level.send(ResetLevel::Switching);
 }
-
-// FIXME: temp code with lots of copied stuff to impl audio changing
-pub fn set_bgm_from_current_level(
-    current_level: Res<CurrentLevel>,
-    mut ev_change_bgm: EventWriter<ChangeBgmEvent>,
-    ldtk_projects: Query<&LdtkProjectHandle>,
-    ldtk_project_assets: Res<Assets<LdtkProject>>,
-) {
-    let Ok(ldtk_handle) = ldtk_projects.get_single() else {
-        return;
-    };
-    let Ok(ldtk_levels) = get_ldtk_level_data(ldtk_project_assets.into_inner(), ldtk_handle) else {
-        return;
-    };
-    let cur_id = ldtk_levels.iter().find_map(|level| {
-        let level_id = level
-            .get_string_field("LevelId")
-            .expect("Levels should always have a level id!");
-        if level_id.is_empty() {
-            panic!("Level id for a level should not be empty!");
-        }
-        if level.iid == current_level.level_iid.as_str() {
-            return Some(level_id);
-        }
-        None
-    });
-
-    let new_bgm = match cur_id {
-        Some(val) if &val[0..1] == "2" || &val[0..1] == "1" => BgmTrack::MustntStop,
-        Some(val) if &val[0..1] == "3" => BgmTrack::Cutscene1Draft,
-        Some(val) if &val[0..1] == "4" => BgmTrack::LightInTheDark,
-        _ => BgmTrack::None,
-    };
-
-    ev_change_bgm.send(ChangeBgmEvent(new_bgm));
-}
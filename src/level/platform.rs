@@ -12,7 +12,10 @@ use crate::{
     shared::{GroupLabel, ResetLevel},
 };
 
-use super::{CurrentLevel, LevelSystems};
+use super::{
+    entity::{resolve_fallible, Fallible, LdtkEntityError},
+    CurrentLevel, LevelSystems,
+};
 
 const PLAYER_WIDTH: f32 = 16.0;
 const PLAYER_HEIGHT: f32 = 19.0;
@@ -38,6 +41,10 @@ impl Plugin for PlatformPlugin {
         .add_event::<ChangePlatformStateEvent>()
         .add_systems(FixedUpdate, move_platforms.in_set(LevelSystems::Simulation))
         .register_ldtk_entity::<MovingPlatformBundle>("MovingPlatform")
+        .add_systems(
+            PreUpdate,
+            resolve_fallible::<MovingPlatform>.in_set(LevelSystems::Processing),
+        )
         .add_systems(FixedUpdate, reset_platforms.run_if(on_event::<ResetLevel>));
     }
 }
@@ -71,7 +78,7 @@ impl From<&String> for PlatformState {
 }
 
 /// Component to represent a moving platforms
-#[derive(Default, Component)]
+#[derive(Default, Component, Clone, Debug)]
 pub struct MovingPlatform {
     pub path: Vec<IVec2>, // Array of points that the platform will traverse
     pub path_curve_points: Vec<bool>, // Array of booleans determining circular motion of platform
@@ -246,32 +253,43 @@ impl MovingPlatform {
 }
 
 // Setting initial platform values and obtaining LDtk fields
-impl From<&bevy_ecs_ldtk::EntityInstance> for MovingPlatform {
-    fn from(entity_instance: &bevy_ecs_ldtk::EntityInstance) -> Self {
-        let mut path = match &entity_instance.get_field_instance("path").unwrap().value {
+impl TryFrom<&bevy_ecs_ldtk::EntityInstance> for MovingPlatform {
+    type Error = LdtkEntityError;
+
+    fn try_from(entity_instance: &bevy_ecs_ldtk::EntityInstance) -> Result<Self, Self::Error> {
+        let mut path = match &entity_instance.get_field_instance("path")?.value {
             FieldValue::Points(val) => val.clone().into_iter().flatten().collect::<Vec<IVec2>>(),
-            _ => panic!("Unexpected data type!"),
+            _ => return Err(LdtkEntityError::new("path should be an array of points")),
         };
         let mut path_curve_points = match &entity_instance
-            .get_field_instance("path_curve_points")
-            .unwrap()
+            .get_field_instance("path_curve_points")?
             .value
         {
             FieldValue::Bools(val) => val.clone(),
-            _ => panic!("Unexpected data type!"),
+            _ => {
+                return Err(LdtkEntityError::new(
+                    "path_curve_points should be an array of bools",
+                ))
+            }
         };
         if path_curve_points.is_empty() {
             for _ in path.iter() {
                 path_curve_points.insert(0, false);
             }
         }
+        if path_curve_points.len() < path.len() {
+            return Err(LdtkEntityError::new(
+                "path_curve_points needs a value for every point in path",
+            ));
+        }
         path_curve_points.insert(0, false);
-        let speed = *entity_instance.get_float_field("speed").unwrap();
-        let initial_state =
-            PlatformState::from(entity_instance.get_enum_field("DefaultState").unwrap());
+        let speed = *entity_instance.get_float_field("speed")?;
+        let initial_state = PlatformState::from(entity_instance.get_enum_field("DefaultState")?);
         let width = entity_instance.width;
         let height = entity_instance.height;
-        let curr_segment = path[0];
+        let Some(&curr_segment) = path.first() else {
+            return Err(LdtkEntityError::new("path needs at least one point"));
+        };
         let curr_segment_index = match path.len() {
             0 => 0,
             _ => 1,
@@ -284,14 +302,18 @@ impl From<&bevy_ecs_ldtk::EntityInstance> for MovingPlatform {
         let previous_segment = initial_pos;
         let curr_state = initial_state;
         //let curr_direction = PlatformDirection::Forward;
-        let does_reverse = *entity_instance.get_bool_field("does_reverse").unwrap();
+        let does_reverse = *entity_instance.get_bool_field("does_reverse")?;
         if does_reverse && path_curve_points[path_curve_points.len() - 1] {
-            panic!("Last element of path_curve_points cannot be a curve if the platform reverses!");
+            return Err(LdtkEntityError::new(
+                "Last element of path_curve_points cannot be a curve if the platform reverses!",
+            ));
         }
         let mut last_point = path_curve_points[0];
         for point in path_curve_points[1..].iter() {
             if last_point && last_point == *point {
-                panic!("Elements in path_curve_points cannot be adjacent!");
+                return Err(LdtkEntityError::new(
+                    "Elements in path_curve_points cannot be adjacent!",
+                ));
             } else {
                 last_point = *point;
             }
@@ -307,14 +329,14 @@ impl From<&bevy_ecs_ldtk::EntityInstance> for MovingPlatform {
             path_curve_points.append(&mut reversed_path_curve_points);
         }
 
-        let does_repeat = *entity_instance.get_bool_field("does_repeat").unwrap();
-        let can_reactivate = *entity_instance.get_bool_field("can_reactivate").unwrap();
+        let does_repeat = *entity_instance.get_bool_field("does_repeat")?;
+        let can_reactivate = *entity_instance.get_bool_field("can_reactivate")?;
         let has_activated = false;
-        let id = *entity_instance.get_int_field("event_id").unwrap();
+        let id = *entity_instance.get_int_field("event_id")?;
         let arc_time = 0.0;
         let current_position = initial_pos.as_vec2();
 
-        MovingPlatform {
+        Ok(MovingPlatform {
             path,
             path_curve_points,
             initial_state,
@@ -331,7 +353,7 @@ impl From<&bevy_ecs_ldtk::EntityInstance> for MovingPlatform {
             id,
             arc_time,
             current_position,
-        }
+        })
     }
 }
 
@@ -364,7 +386,7 @@ impl Default for PlatformPhysicsBundle {
 #[derive(Default, Bundle, LdtkEntity)]
 pub struct MovingPlatformBundle {
     #[from_entity_instance]
-    pub platform: MovingPlatform,
+    pub platform: Fallible<MovingPlatform>,
     #[grid_coords]
     pub grid_coords: GridCoords,
     #[sprite_sheet]
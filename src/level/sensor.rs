@@ -13,8 +13,10 @@ use crate::{
 };
 
 use super::{
-    crystal::CrystalColor, entity::FixedEntityBundle, platform::PlatformState, LevelSystems,
-    LightColor,
+    crystal::CrystalColor,
+    entity::{resolve_fallible, Fallible, FixedEntityBundle, LdtkEntityError},
+    platform::PlatformState,
+    LevelSystems, LightColor,
 };
 
 pub struct LightSensorPlugin;
@@ -24,7 +26,9 @@ impl Plugin for LightSensorPlugin {
         app.register_ldtk_entity::<LightSensorBundle>("Sensor")
             .add_systems(
                 PreUpdate,
-                add_sensor_sprites.in_set(LevelSystems::Processing),
+                (resolve_fallible::<LightSensor>, add_sensor_sprites)
+                    .chain()
+                    .in_set(LevelSystems::Processing),
             )
             .add_systems(Update, reset_light_sensors.in_set(LevelSystems::Reset))
             .add_systems(
@@ -38,7 +42,7 @@ impl Plugin for LightSensorPlugin {
 
 /// [`Component`] added to entities receptive to light. The
 /// [`activation_timer`](LightSensor::activation_timer) should be initialized in the
-/// `From<&EntityInstance>` implemenation for the [`LightSensorBundle`], if not default.
+/// `TryFrom<&EntityInstance>` implemenation for the [`LightSensorBundle`], if not default.
 ///
 /// The [`Sprite`] on the entity containing a [`LightSensor`] refers to the center part of the
 /// sprite, which will be colored depending on the light that hits it.
@@ -95,20 +99,15 @@ impl LightSensor {
     }
 }
 
-impl From<&EntityInstance> for LightSensor {
-    fn from(entity_instance: &EntityInstance) -> Self {
-        let toggle_color: CrystalColor = entity_instance
-            .get_enum_field("toggle_color")
-            .expect("toggle_color needs to be an enum field on all sensors")
-            .into();
+impl TryFrom<&EntityInstance> for LightSensor {
+    type Error = LdtkEntityError;
 
-        let id = entity_instance
-            .get_int_field("id")
-            .expect("id needs to be an int field on all sensors");
+    fn try_from(entity_instance: &EntityInstance) -> Result<Self, Self::Error> {
+        let toggle_color = CrystalColor::try_from(entity_instance.get_enum_field("toggle_color")?)?;
 
-        let millis = *entity_instance
-            .get_int_field("activation_time")
-            .expect("activation_time needs to be a float field on all sensors");
+        let id = entity_instance.get_int_field("id")?;
+
+        let millis = *entity_instance.get_int_field("activation_time")?;
 
         let toggle_ident = CrystalIdent {
             color: toggle_color,
@@ -120,7 +119,7 @@ impl From<&EntityInstance> for LightSensor {
             Err(_) => -1,
         };
 
-        LightSensor::new(toggle_ident, millis, platform_id)
+        Ok(LightSensor::new(toggle_ident, millis, platform_id))
     }
 }
 
@@ -164,16 +163,18 @@ pub struct LightSensorBundle {
     #[default]
     sensor: Sensor,
     #[from_entity_instance]
-    light_sensor: LightSensor,
+    light_sensor: Fallible<LightSensor>,
     #[with(sensor_point_light)]
     lighting: LineLight2d,
 }
 
 pub fn sensor_point_light(entity_instance: &EntityInstance) -> LineLight2d {
-    let toggle_color: CrystalColor = entity_instance
+    // broken sensors are reported when converting the LightSensor
+    let toggle_color = entity_instance
         .get_enum_field("toggle_color")
-        .expect("toggle_color needs to be an enum field on all sensors")
-        .into();
+        .ok()
+        .and_then(|toggle_color| CrystalColor::try_from(toggle_color).ok())
+        .unwrap_or_default();
 
     LineLight2d::point(
         toggle_color
@@ -1,13 +1,19 @@
-use crate::config::Config;
+use crate::level_pack::LevelPacks;
 use crate::shared::GameState;
 use bevy::prelude::*;
-use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::{assets::LdtkExternalLevel, prelude::*, systems::apply_level_selection};
+
+use super::get_loaded_level;
+
+/// The identifier of Lyra's LDtk entity.
+const LYRA_IDENT: &str = "Lyra";
 
 pub struct LevelSetupPlugin;
 
 impl Plugin for LevelSetupPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(LevelSelection::index(17))
+        // replaced by the level Lyra is in once the project has loaded, see select_lyra_level
+        app.insert_resource(LevelSelection::index(0))
             .insert_resource(LdtkSettings {
                 level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
                     load_level_neighbors: true,
@@ -15,7 +21,8 @@ impl Plugin for LevelSetupPlugin {
                 level_background: LevelBackground::Nonexistent,
                 ..default()
             })
-            .add_systems(Startup, setup_level);
+            .add_systems(Startup, setup_level)
+            .add_systems(Update, select_lyra_level.before(apply_level_selection));
     }
 }
 
@@ -23,11 +30,58 @@ pub fn setup_level(
     mut commands: Commands,
     mut next_game_state: ResMut<NextState<GameState>>,
     asset_server: Res<AssetServer>,
-    config: Res<Config>,
+    level_packs: Res<LevelPacks>,
 ) {
     commands.spawn(LdtkWorldBundle {
-        ldtk_handle: asset_server.load(&config.level_config.level_path).into(),
+        ldtk_handle: asset_server.load(&level_packs.active().path).into(),
         ..Default::default()
     });
     next_game_state.set(GameState::Ui);
 }
+
+/// Finds the index of the level Lyra is placed in, which has to be spawned first. She is moved to
+/// other levels from there by the level select and `--level`.
+pub fn lyra_level_index(
+    ldtk_project: &LdtkProject,
+    external_levels: &Assets<LdtkExternalLevel>,
+) -> Option<usize> {
+    ldtk_project.json_data().levels.iter().position(|level| {
+        get_loaded_level(ldtk_project, external_levels, &level.iid).is_some_and(|level| {
+            level
+                .layer_instances()
+                .iter()
+                .flat_map(|layer| &layer.entity_instances)
+                .any(|entity| entity.identifier == LYRA_IDENT)
+        })
+    })
+}
+
+/// [`System`] that selects the level Lyra is in once a newly spawned LDtk project has loaded, so
+/// that she is spawned along with it.
+fn select_lyra_level(
+    q_ldtk: Query<&LdtkProjectHandle>,
+    asset_server: Res<AssetServer>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    external_levels: Res<Assets<LdtkExternalLevel>>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut selected_project: Local<Option<AssetId<LdtkProject>>>,
+) {
+    let Ok(ldtk_handle) = q_ldtk.get_single() else {
+        return;
+    };
+    let project_id = AssetId::from(ldtk_handle);
+    if *selected_project == Some(project_id)
+        || !asset_server.is_loaded_with_dependencies(project_id)
+    {
+        return;
+    }
+    let Some(ldtk_project) = ldtk_assets.get(project_id) else {
+        return;
+    };
+    *selected_project = Some(project_id);
+
+    match lyra_level_index(ldtk_project, &external_levels) {
+        Some(index) => *level_selection = LevelSelection::index(index),
+        None => error!("None of the levels have a {LYRA_IDENT} entity, so there is no player"),
+    }
+}
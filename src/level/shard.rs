@@ -24,7 +24,10 @@ use crate::{
     sound::{BgmMarker, Fade, FadeSettings, BGM_VOLUME},
 };
 
-use super::{entity::FixedEntityBundle, CurrentLevel, LevelSystems};
+use super::{
+    entity::{resolve_fallible, Fallible, FixedEntityBundle, LdtkEntityError},
+    CurrentLevel, LevelSystems,
+};
 
 pub struct CrystalShardPlugin;
 
@@ -36,7 +39,9 @@ impl Plugin for CrystalShardPlugin {
             .register_ldtk_entity::<CrystalShardBundle>("CrystalShard")
             .add_systems(
                 PreUpdate,
-                add_crystal_shard_sprites.in_set(LevelSystems::Processing),
+                (resolve_fallible::<CrystalShard>, add_crystal_shard_sprites)
+                    .chain()
+                    .in_set(LevelSystems::Processing),
             )
             // FIXME: if the player is holding down the mouse button while collecting a shard,
             // the preview and angle indicator will stay. need to potentially consider a input
@@ -72,21 +77,20 @@ pub struct CrystalShard {
     light_color: LightColor,
 }
 
-impl From<&EntityInstance> for CrystalShard {
-    fn from(value: &EntityInstance) -> Self {
-        let light_color = value
-            .get_enum_field("light_color")
-            .expect("All crystal shards should have a light_color enum field")
-            .into();
+impl TryFrom<&EntityInstance> for CrystalShard {
+    type Error = LdtkEntityError;
+
+    fn try_from(value: &EntityInstance) -> Result<Self, Self::Error> {
+        let light_color = LightColor::try_from(value.get_enum_field("light_color")?)?;
 
-        Self { light_color }
+        Ok(Self { light_color })
     }
 }
 
 #[derive(Bundle, LdtkEntity)]
 pub struct CrystalShardBundle {
     #[from_entity_instance]
-    shard: CrystalShard,
+    shard: Fallible<CrystalShard>,
     #[from_entity_instance]
     physics: FixedEntityBundle,
     #[with(crystal_shard_light)]
@@ -96,10 +100,12 @@ pub struct CrystalShardBundle {
 }
 
 pub fn crystal_shard_light(entity_instance: &EntityInstance) -> LineLight2d {
-    let light_color: LightColor = entity_instance
+    // broken shards are reported when converting the CrystalShard
+    let light_color = entity_instance
         .get_enum_field("light_color")
-        .expect("All crystal shards should have a light_color enum field")
-        .into();
+        .ok()
+        .and_then(|light_color| LightColor::try_from(light_color).ok())
+        .unwrap_or_default();
 
     LineLight2d::point(light_color.lighting_color().extend(1.0), 40.0, 0.015)
 }
@@ -1,5 +1,12 @@
 use bevy::prelude::*;
-use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::{
+    ldtk::{loaded_level::LoadedLevel, Type},
+    prelude::*,
+};
+
+use crate::shared::LYRA_RESPAWN_EPSILON;
+
+const START_FLAG_IDENT: &str = "Start";
 
 /// [`Component`] to mark start flags in the level. Used to query for when start flags are loaded
 /// by Ldtk.
@@ -46,3 +53,21 @@ pub fn init_start_marker(
         });
     }
 }
+
+/// Finds where the player spawns at the start flag of a level, from the LDtk data of the level
+/// rather than the spawned [`StartFlag`], so that it works for levels that haven't been spawned.
+/// See [`get_loaded_level`](super::get_loaded_level). Returns [`None`] if the level has no start
+/// flag, or the LDtk world isn't in Free layout.
+pub fn start_flag_position(level: LoadedLevel) -> Option<Vec2> {
+    let start_flag = level
+        .layer_instances()
+        .iter()
+        .filter(|layer| layer.layer_instance_type == Type::Entities)
+        .flat_map(|layer| &layer.entity_instances)
+        .find(|entity| entity.identifier == START_FLAG_IDENT)?;
+    let (Some(x), Some(y)) = (start_flag.world_x, start_flag.world_y) else {
+        error!("Start flag entity has no coordinates! (This is probably because your LDTK world is not in free layout mode.)");
+        return None;
+    };
+    Some(Vec2::new(x as f32, -y as f32 + LYRA_RESPAWN_EPSILON))
+}
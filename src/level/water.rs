@@ -0,0 +1,299 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{AlphaMode2d, Material2d, Material2dPlugin},
+};
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    camera::{MainCamera, HIGHRES_LAYER},
+    particle::splash::SplashEvent,
+    player::{
+        movement::{move_player, PlayerMovement},
+        PlayerMarker,
+    },
+};
+
+use super::LevelSystems;
+
+/// The path to the shader used by the [`WaterMaterial`]
+const WATER_SHADER_PATH: &str = "shaders/water.wgsl";
+
+/// The depth of [`Water`] relative to the [`MainCamera`]. The pixelated terrain is drawn at the
+/// camera's depth and the background at `-5.0`, so this places water between the two.
+const WATER_Z_OFFSET: f32 = -1.0;
+
+/// The index of refraction of water, used to bend light beams that cross its surface.
+pub const WATER_REFRACTIVE_INDEX: f32 = 1.33;
+
+pub struct WaterPlugin;
+
+impl Plugin for WaterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<WaterMaterial>::default())
+            .register_ldtk_entity::<WaterBundle>("Water")
+            .add_systems(PreUpdate, add_water_meshes.in_set(LevelSystems::Processing))
+            .add_systems(Update, sync_water_z)
+            .add_systems(
+                FixedUpdate,
+                submerge_player
+                    .before(move_player)
+                    .in_set(LevelSystems::Simulation),
+            );
+    }
+}
+
+/// [`Component`] for rectangular volumes of water. The player swims while inside of one, and light
+/// beams are refracted when crossing its edges.
+#[derive(Component, Debug)]
+pub struct Water {
+    pub half_extent: Vec2,
+}
+
+impl From<&EntityInstance> for Water {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Water {
+            half_extent: Vec2::new(
+                entity_instance.width as f32 / 2.0,
+                entity_instance.height as f32 / 2.0,
+            ),
+        }
+    }
+}
+
+impl Water {
+    pub fn rect(&self, transform: &GlobalTransform) -> Rect {
+        Rect::from_center_half_size(transform.translation().truncate(), self.half_extent)
+    }
+}
+
+/// [`Bundle`] spawned in by Ldtk corresponding to water volumes.
+#[derive(Bundle, LdtkEntity)]
+pub struct WaterBundle {
+    #[from_entity_instance]
+    water: Water,
+}
+
+/// Marker [`Component`] for the mesh drawing a [`Water`] volume.
+#[derive(Component)]
+pub struct WaterMesh;
+
+/// Custom [`Material2d`] that draws translucent, animated [`Water`].
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct WaterMaterial {
+    #[uniform(0)]
+    pub color: LinearRgba,
+    #[uniform(1)]
+    pub surface_color: LinearRgba,
+    /// The size of the volume in pixels, so that the surface line has a constant thickness.
+    #[uniform(2)]
+    pub size: Vec2,
+    // WebGL2 requires this struct be 16-byte aligned
+    pub _wasm_padding: Vec2,
+}
+
+impl Material2d for WaterMaterial {
+    fn fragment_shader() -> ShaderRef {
+        WATER_SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+/// [`System`] that spawns the translucent mesh for newly added [`Water`] volumes.
+pub fn add_water_meshes(
+    mut commands: Commands,
+    q_water: Query<(Entity, &Water), Added<Water>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<WaterMaterial>>,
+) {
+    for (entity, water) in q_water.iter() {
+        let size = water.half_extent * 2.0;
+        commands.entity(entity).with_child((
+            WaterMesh,
+            Mesh2d(meshes.add(Rectangle::from_size(size))),
+            MeshMaterial2d(materials.add(WaterMaterial {
+                color: Color::srgba(0.2, 0.45, 0.8, 0.45).into(),
+                surface_color: Color::srgba(0.7, 0.9, 1.0, 0.8).into(),
+                size,
+                _wasm_padding: Vec2::ZERO,
+            })),
+            Transform::default(),
+            HIGHRES_LAYER,
+        ));
+    }
+}
+
+/// [`System`] that keeps [`WaterMesh`]es behind the terrain, since the [`Water`] entities
+/// themselves live on an entity layer drawn above it.
+pub fn sync_water_z(
+    q_camera: Query<&GlobalTransform, With<MainCamera>>,
+    q_water: Query<(&GlobalTransform, &Children), With<Water>>,
+    mut q_water_mesh: Query<&mut Transform, With<WaterMesh>>,
+) {
+    let Ok(camera_transform) = q_camera.get_single() else {
+        return;
+    };
+    let water_z = camera_transform.translation().z + WATER_Z_OFFSET;
+    for (water_transform, children) in q_water.iter() {
+        for child in children.iter() {
+            let Ok(mut transform) = q_water_mesh.get_mut(*child) else {
+                continue;
+            };
+            transform.translation.z = water_z - water_transform.translation().z;
+        }
+    }
+}
+
+/// [`System`] that marks the player as submerged when they are inside of [`Water`], and splashes
+/// when they fall through the surface.
+pub fn submerge_player(
+    mut q_player: Query<(&Transform, &mut PlayerMovement), With<PlayerMarker>>,
+    q_water: Query<(&Water, &GlobalTransform)>,
+    mut ev_splash: EventWriter<SplashEvent>,
+    mut last_player_pos: Local<Option<Vec2>>,
+) {
+    let Ok((transform, mut movement)) = q_player.get_single_mut() else {
+        return;
+    };
+    let player_pos = transform.translation.truncate();
+    let water_rect = q_water
+        .iter()
+        .map(|(water, water_transform)| water.rect(water_transform))
+        .find(|rect| rect.contains(player_pos));
+
+    if let (Some(rect), Some(last_pos)) = (water_rect, *last_player_pos) {
+        if !movement.submerged {
+            if let Some(pos) = surface_crossing(rect, last_pos, player_pos) {
+                ev_splash.send(SplashEvent { pos });
+            }
+        }
+    }
+    movement.submerged = water_rect.is_some();
+    *last_player_pos = Some(player_pos);
+}
+
+/// Finds where the movement from `from` to `to` crossed the surface of the water in `rect`, if it
+/// entered through the top edge rather than a side or the bottom.
+fn surface_crossing(rect: Rect, from: Vec2, to: Vec2) -> Option<Vec2> {
+    if from.y <= rect.max.y || to.y > rect.max.y {
+        return None;
+    }
+    let t = (from.y - rect.max.y) / (from.y - to.y);
+    let x = from.x.lerp(to.x, t);
+    (rect.min.x..=rect.max.x)
+        .contains(&x)
+        .then_some(Vec2::new(x, rect.max.y))
+}
+
+/// Finds the first edge of a [`Water`] volume crossed by the ray within `max_toi`, returning the
+/// water entity, the time of impact, the normal of the edge facing against the ray, and whether
+/// the ray is entering the water.
+pub fn cast_ray_through_water(
+    q_water: &Query<(Entity, &Water, &GlobalTransform)>,
+    ray_pos: Vec2,
+    ray_dir: Vec2,
+    max_toi: f32,
+) -> Option<(Entity, f32, Vec2, bool)> {
+    const EPSILON: f32 = 0.01;
+
+    let mut closest = None;
+    for (entity, water, transform) in q_water.iter() {
+        let rect = water.rect(transform);
+
+        let mut t_enter = f32::NEG_INFINITY;
+        let mut t_exit = f32::INFINITY;
+        let mut enter_normal = Vec2::ZERO;
+        let mut exit_normal = Vec2::ZERO;
+        for axis in 0..2 {
+            let (pos, dir) = (ray_pos[axis], ray_dir[axis]);
+            let (min, max) = (rect.min[axis], rect.max[axis]);
+            if dir.abs() < f32::EPSILON {
+                if pos < min || pos > max {
+                    t_exit = f32::NEG_INFINITY;
+                }
+                continue;
+            }
+            let mut normal = Vec2::ZERO;
+            normal[axis] = -dir.signum();
+            let (t1, t2) = ((min - pos) / dir, (max - pos) / dir);
+            if t1.min(t2) > t_enter {
+                t_enter = t1.min(t2);
+                enter_normal = normal;
+            }
+            if t1.max(t2) < t_exit {
+                t_exit = t1.max(t2);
+                exit_normal = normal;
+            }
+        }
+        if t_enter > t_exit {
+            continue;
+        }
+
+        let crossing = if t_enter > EPSILON {
+            (t_enter, enter_normal, true)
+        } else if t_exit > EPSILON {
+            (t_exit, exit_normal, false)
+        } else {
+            continue;
+        };
+        if crossing.0 < max_toi && closest.is_none_or(|(_, toi, _, _)| crossing.0 < toi) {
+            closest = Some((entity, crossing.0, crossing.1, crossing.2));
+        }
+    }
+    closest
+}
+
+/// Bends `ray_dir` as it crosses the surface of [`Water`]. Rays that hit the surface from inside
+/// at too shallow of an angle are reflected instead (total internal reflection).
+pub fn refract_through_water(ray_dir: Vec2, normal: Vec2, entering: bool) -> Vec2 {
+    let eta = if entering {
+        1.0 / WATER_REFRACTIVE_INDEX
+    } else {
+        WATER_REFRACTIVE_INDEX
+    };
+    let refracted = ray_dir.refract(normal, eta);
+    if refracted == Vec2::ZERO {
+        ray_dir.reflect(normal)
+    } else {
+        refracted.normalize()
+    }
+}
+
+// WebGL2 requires these structs be 16-byte aligned
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem;
+
+    #[test]
+    fn water_material_alignment() {
+        assert_eq!(mem::size_of::<WaterMaterial>() % 16, 0);
+    }
+
+    #[test]
+    fn only_entering_through_the_surface_splashes() {
+        let rect = Rect::new(0.0, 0.0, 100.0, 50.0);
+        assert_eq!(
+            surface_crossing(rect, Vec2::new(10.0, 60.0), Vec2::new(30.0, 40.0)),
+            Some(Vec2::new(20.0, 50.0))
+        );
+        // from the side
+        assert_eq!(
+            surface_crossing(rect, Vec2::new(-5.0, 40.0), Vec2::new(5.0, 40.0)),
+            None
+        );
+        // from below
+        assert_eq!(
+            surface_crossing(rect, Vec2::new(50.0, -5.0), Vec2::new(50.0, 5.0)),
+            None
+        );
+        // over the corner, above the surface of the water
+        assert_eq!(
+            surface_crossing(rect, Vec2::new(-20.0, 60.0), Vec2::new(5.0, 45.0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    particle::Wind,
+    player::{
+        movement::{move_player, PlayerMovement},
+        PlayerMarker,
+    },
+};
+
+use super::{
+    entity::{resolve_fallible, Fallible, LdtkEntityError},
+    LevelSystems,
+};
+
+/// How much the noise driven gusts can scale the strength of a gusty [`WindZone`].
+const GUST_AMOUNT: f32 = 0.75;
+
+pub struct WindPlugin;
+
+impl Plugin for WindPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<WindZoneBundle>("WindZone")
+            .add_systems(
+                PreUpdate,
+                resolve_fallible::<WindZone>.in_set(LevelSystems::Processing),
+            )
+            .add_systems(
+                FixedUpdate,
+                push_player_with_wind
+                    .before(move_player)
+                    .in_set(LevelSystems::Simulation),
+            );
+    }
+}
+
+/// [`Component`] for rectangular areas that push the player, particles and strands in a fixed
+/// direction.
+#[derive(Component, Debug)]
+pub struct WindZone {
+    pub half_extent: Vec2,
+    /// The velocity added to the player every [`FixedUpdate`] while inside the zone.
+    pub force: Vec2,
+    /// Whether the strength of the zone should be scaled by the [`Wind`] noise.
+    pub gusty: bool,
+}
+
+impl TryFrom<&EntityInstance> for WindZone {
+    type Error = LdtkEntityError;
+
+    fn try_from(entity_instance: &EntityInstance) -> Result<Self, Self::Error> {
+        let direction = *entity_instance.get_float_field("direction")?;
+        let strength = *entity_instance.get_float_field("strength")?;
+        let gusty = match entity_instance.get_bool_field("gusty") {
+            Ok(gusty) => *gusty,
+            Err(_) => false,
+        };
+
+        Ok(WindZone {
+            half_extent: Vec2::new(
+                entity_instance.width as f32 / 2.0,
+                entity_instance.height as f32 / 2.0,
+            ),
+            // LDtk angles are in degrees, counterclockwise from the right
+            force: Vec2::from_angle(direction.to_radians()) * strength,
+            gusty,
+        })
+    }
+}
+
+impl WindZone {
+    fn force_at(&self, center: Vec2, pos: Vec2, wind: &Wind, time: f32) -> Vec2 {
+        if !Rect::from_center_half_size(center, self.half_extent).contains(pos) {
+            return Vec2::ZERO;
+        }
+        if !self.gusty {
+            return self.force;
+        }
+        let gust = 1.0 + wind.force_at(time, pos).x * GUST_AMOUNT;
+        self.force * gust.max(0.0)
+    }
+}
+
+/// [`Bundle`] spawned in by Ldtk corresponding to wind zones.
+#[derive(Bundle, LdtkEntity)]
+pub struct WindZoneBundle {
+    #[from_entity_instance]
+    zone: Fallible<WindZone>,
+}
+
+/// Sums the force of every [`WindZone`] that contains `pos`.
+pub fn wind_zone_force_at(
+    q_wind_zones: &Query<(&WindZone, &GlobalTransform)>,
+    wind: &Wind,
+    time: f32,
+    pos: Vec2,
+) -> Vec2 {
+    q_wind_zones
+        .iter()
+        .map(|(zone, transform)| zone.force_at(transform.translation().truncate(), pos, wind, time))
+        .sum()
+}
+
+/// [`System`] that stores the force of the wind zones the player is in, so that it can be added to
+/// the player's velocity in [`move_player`].
+pub fn push_player_with_wind(
+    mut q_player: Query<(&Transform, &mut PlayerMovement), With<PlayerMarker>>,
+    q_wind_zones: Query<(&WindZone, &GlobalTransform)>,
+    wind: Res<Wind>,
+    time: Res<Time>,
+) {
+    let Ok((transform, mut movement)) = q_player.get_single_mut() else {
+        return;
+    };
+    movement.wind = wind_zone_force_at(
+        &q_wind_zones,
+        &wind,
+        time.elapsed_secs(),
+        transform.translation.truncate(),
+    );
+}
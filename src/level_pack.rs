@@ -0,0 +1,402 @@
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::config::Config;
+use crate::input::action::InputAction;
+use crate::level::hot_reload::LevelReloadEvent;
+use crate::save::SaveData;
+use crate::shared::{GameState, UiState};
+use crate::sound::{BgmTrack, ChangeBgmEvent};
+
+/// The directory of the game's own level packs, relative to the assets directory.
+const LEVELS_DIR: &str = "levels";
+
+/// The name of the [`AssetSource`](bevy::asset::io::AssetSource) of the player's own level packs,
+/// so that their LDtk projects load from paths such as `mods://my-pack.ldtk`.
+pub const MODS_SOURCE: &str = "mods";
+
+/// Level fields the game can't play a level without, see
+/// [`switch_level`](crate::level::switch_level). Projects without them aren't listed as packs.
+const REQUIRED_LEVEL_FIELDS: [&str; 2] = ["LevelId", "AllowedColors"];
+
+/// [`Plugin`] that registers the `mods://` [`AssetSource`](bevy::asset::io::AssetSource), which
+/// reads from the `mods` directory next to the saves. It has to be added before the
+/// [`AssetPlugin`].
+///
+/// `bevy_ecs_ldtk` resolves the paths in a project without its asset source, so the tilesets of a
+/// mod are loaded from the game's assets directory.
+pub struct ModsAssetSourcePlugin;
+
+impl Plugin for ModsAssetSourcePlugin {
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    fn build(&self, app: &mut App) {
+        use bevy::asset::io::AssetSourceBuilder;
+
+        let Some(mods_dir) = discovery::mods_dir() else {
+            return;
+        };
+        // the file watcher can't watch a directory that doesn't exist
+        if let Err(err) = std::fs::create_dir_all(&mods_dir) {
+            // logging isn't set up before the DefaultPlugins, so warn once the app starts
+            let warning = format!("Could not create {}: {err}", mods_dir.display());
+            app.add_systems(Startup, move || warn!("{warning}"));
+            return;
+        }
+        app.register_asset_source(
+            MODS_SOURCE,
+            AssetSourceBuilder::platform_default(&mods_dir.to_string_lossy(), None),
+        );
+    }
+
+    #[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
+    fn build(&self, _app: &mut App) {}
+}
+
+/// [`Plugin`] for the level pack menu, which switches between the LDtk projects in the levels
+/// directory and the `mods://` source. Each pack keeps its own progress in the [`SaveData`].
+pub struct LevelPackPlugin;
+
+impl Plugin for LevelPackPlugin {
+    fn build(&self, app: &mut App) {
+        let level_path = app
+            .world()
+            .get_resource::<Config>()
+            .map(|config| config.level_config.level_path.clone())
+            .unwrap_or_default();
+
+        app.insert_resource(LevelPacks::discover(&level_path))
+            .add_event::<SwitchLevelPackEvent>()
+            .add_systems(
+                PostUpdate,
+                switch_to_level_packs.run_if(input_just_pressed(InputAction::LevelPacks)),
+            )
+            .add_systems(
+                Update,
+                switch_level_pack.run_if(on_event::<SwitchLevelPackEvent>),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    spawn_level_packs.run_if(in_state(UiState::LevelPacks)),
+                    handle_level_pack_buttons.run_if(in_state(UiState::LevelPacks)),
+                    despawn_level_packs.run_if(not(in_state(UiState::LevelPacks))),
+                ),
+            );
+    }
+}
+
+/// An LDtk project that can be played on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelPack {
+    /// The name shown in the level pack menu, which is the file name without the extension.
+    pub name: String,
+    /// The asset path of the LDtk project, which is also the key of the pack's progress in the
+    /// [`SaveData`].
+    pub path: String,
+}
+
+impl LevelPack {
+    fn from_path(path: String) -> Self {
+        let name = std::path::Path::new(&path)
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.clone());
+        LevelPack { name, path }
+    }
+}
+
+/// [`Resource`] holding every [`LevelPack`] that was found on startup, and the one that is
+/// spawned.
+#[derive(Resource, Debug)]
+pub struct LevelPacks {
+    pub packs: Vec<LevelPack>,
+    active: usize,
+}
+
+impl LevelPacks {
+    /// Finds the level packs in the levels directory and the `mods://` source. The project at
+    /// `level_path` is always a pack, and the one that is spawned first.
+    fn discover(level_path: &str) -> Self {
+        let mut packs = discovery::level_packs();
+        let active = match packs.iter().position(|pack| pack.path == level_path) {
+            Some(active) => active,
+            None => {
+                packs.insert(0, LevelPack::from_path(level_path.to_owned()));
+                0
+            }
+        };
+        LevelPacks { packs, active }
+    }
+
+    pub fn active(&self) -> &LevelPack {
+        &self.packs[self.active]
+    }
+}
+
+/// [`Event`] sent to despawn the LDtk world and spawn the [`LevelPack`] at an index of
+/// [`LevelPacks::packs`] instead.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SwitchLevelPackEvent(pub usize);
+
+/// Finding level packs on the filesystem.
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+mod discovery {
+    use std::{
+        fs, io,
+        path::{Path, PathBuf},
+    };
+
+    use bevy::{asset::io::file::FileAssetReader, prelude::*};
+
+    use super::{LevelPack, LEVELS_DIR, MODS_SOURCE, REQUIRED_LEVEL_FIELDS};
+
+    pub fn mods_dir() -> Option<PathBuf> {
+        Some(dirs::data_dir()?.join("lightborne").join(MODS_SOURCE))
+    }
+
+    pub fn level_packs() -> Vec<LevelPack> {
+        let levels_dir = FileAssetReader::get_base_path()
+            .join("assets")
+            .join(LEVELS_DIR);
+        let mut packs = packs_in_dir(&levels_dir, &format!("{LEVELS_DIR}/"));
+        if let Some(mods_dir) = mods_dir() {
+            packs.extend(packs_in_dir(&mods_dir, &format!("{MODS_SOURCE}://")));
+        }
+        packs
+    }
+
+    /// Lists the playable LDtk projects directly inside `dir` by name, with asset paths starting
+    /// with `path_prefix`.
+    pub(super) fn packs_in_dir(dir: &Path, path_prefix: &str) -> Vec<LevelPack> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(err) => {
+                error!("Could not look for level packs in {}: {err}", dir.display());
+                return Vec::new();
+            }
+        };
+        let mut packs: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "ldtk"))
+            .filter(|path| match is_playable(path) {
+                Ok(playable) => {
+                    if !playable {
+                        info!(
+                            "Skipping {}, its levels don't have the fields {REQUIRED_LEVEL_FIELDS:?}",
+                            path.display()
+                        );
+                    }
+                    playable
+                }
+                Err(err) => {
+                    error!("Skipping {}: {err}", path.display());
+                    false
+                }
+            })
+            .filter_map(|path| {
+                let file_name = path.file_name()?.to_str()?;
+                Some(LevelPack::from_path(format!("{path_prefix}{file_name}")))
+            })
+            .collect();
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+        packs
+    }
+
+    /// Checks that the levels of a project have the [`REQUIRED_LEVEL_FIELDS`].
+    fn is_playable(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+        let project: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let level_fields: Vec<_> = project["defs"]["levelFields"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|field| field["identifier"].as_str())
+            .collect();
+        Ok(REQUIRED_LEVEL_FIELDS
+            .iter()
+            .all(|field| level_fields.contains(field)))
+    }
+}
+
+/// Directories can't be listed on the web, so only the configured project is a pack there.
+#[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
+mod discovery {
+    use super::LevelPack;
+
+    pub fn level_packs() -> Vec<LevelPack> {
+        Vec::new()
+    }
+}
+
+fn switch_to_level_packs(
+    mut next_ui_state: ResMut<NextState<UiState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    next_game_state.set(GameState::Ui);
+    next_ui_state.set(UiState::LevelPacks);
+}
+
+/// [`System`] that replaces the LDtk world with the picked [`LevelPack`]. The caches of the old
+/// levels are cleared by the [`LevelReloadEvent`], and the level select is rebuilt from the
+/// progress of the new pack once it has loaded.
+#[allow(clippy::too_many_arguments)]
+fn switch_level_pack(
+    mut commands: Commands,
+    mut ev_switch: EventReader<SwitchLevelPackEvent>,
+    mut level_packs: ResMut<LevelPacks>,
+    q_ldtk_world: Query<Entity, With<LdtkProjectHandle>>,
+    asset_server: Res<AssetServer>,
+    mut ev_reload: EventWriter<LevelReloadEvent>,
+    mut next_ui_state: ResMut<NextState<UiState>>,
+) {
+    let Some(SwitchLevelPackEvent(index)) = ev_switch.read().last().copied() else {
+        return;
+    };
+    let Some(pack) = level_packs.packs.get(index) else {
+        error!("There is no level pack {index}");
+        return;
+    };
+    info!("Switching to level pack {}", pack.name);
+
+    for world in q_ldtk_world.iter() {
+        commands.entity(world).despawn_recursive();
+    }
+    commands.spawn(LdtkWorldBundle {
+        ldtk_handle: asset_server.load(&pack.path).into(),
+        ..default()
+    });
+    level_packs.active = index;
+    ev_reload.send(LevelReloadEvent);
+    next_ui_state.set(UiState::LevelSelect);
+}
+
+#[derive(Component)]
+struct LevelPacksUiMarker;
+
+/// [`Component`] for the button of the [`LevelPack`] at an index of [`LevelPacks::packs`].
+#[derive(Component)]
+struct LevelPackButton(usize);
+
+fn spawn_level_packs(
+    mut commands: Commands,
+    q_level_packs_ui: Query<Entity, With<LevelPacksUiMarker>>,
+    asset_server: Res<AssetServer>,
+    level_packs: Res<LevelPacks>,
+    save_data: Res<SaveData>,
+    mut ev_change_bgm: EventWriter<ChangeBgmEvent>,
+) {
+    if q_level_packs_ui.get_single().is_ok() {
+        return;
+    }
+    let font = TextFont {
+        font: asset_server.load("fonts/Munro.ttf"),
+        ..default()
+    };
+
+    ev_change_bgm.send(ChangeBgmEvent(BgmTrack::LevelSelect));
+
+    commands
+        .spawn((
+            LevelPacksUiMarker,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("Level Packs"), font.clone().with_font_size(36.)));
+            for (i, pack) in level_packs.packs.iter().enumerate() {
+                let complete = save_data.levels.get(&pack.path).map_or(0, |levels| {
+                    levels.values().filter(|progress| progress.complete).count()
+                });
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(320.0),
+                            padding: UiRect::all(Val::Px(8.0)),
+                            border: UiRect::all(Val::Px(2.0)),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BorderColor(if i == level_packs.active {
+                            Color::srgb(0.0, 1.0, 0.0)
+                        } else {
+                            Color::WHITE
+                        }),
+                        LevelPackButton(i),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((Text::new(&pack.name), font.clone().with_font_size(24.)));
+                        parent.spawn((
+                            Text::new(format!("{complete} levels complete")),
+                            font.clone().with_font_size(16.),
+                        ));
+                    });
+            }
+        });
+}
+
+fn despawn_level_packs(
+    mut commands: Commands,
+    q_level_packs_ui: Query<Entity, With<LevelPacksUiMarker>>,
+) {
+    let Ok(entity) = q_level_packs_ui.get_single() else {
+        return;
+    };
+    commands.entity(entity).despawn_recursive();
+}
+
+/// [`System`] that switches to the pressed [`LevelPack`], or goes back to the level select if it
+/// is already spawned.
+fn handle_level_pack_buttons(
+    q_buttons: Query<(&Interaction, &LevelPackButton), Changed<Interaction>>,
+    level_packs: Res<LevelPacks>,
+    mut ev_switch: EventWriter<SwitchLevelPackEvent>,
+    mut next_ui_state: ResMut<NextState<UiState>>,
+) {
+    for (interaction, button) in q_buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if button.0 == level_packs.active {
+            next_ui_state.set(UiState::LevelSelect);
+        } else {
+            ev_switch.send(SwitchLevelPackEvent(button.0));
+        }
+    }
+}
+
+#[cfg(all(test, not(any(target_arch = "wasm32", target_arch = "wasm64"))))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_packs_are_found() {
+        let levels_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join(LEVELS_DIR);
+        let packs = discovery::packs_in_dir(&levels_dir, "levels/");
+        assert!(packs.contains(&LevelPack {
+            name: "lightborne".to_owned(),
+            path: "levels/lightborne.ldtk".to_owned(),
+        }));
+        // made before the level fields were added
+        assert!(packs.iter().all(|pack| pack.name != "david-tran"));
+    }
+
+    #[test]
+    fn configured_project_is_always_a_pack() {
+        let level_packs = LevelPacks::discover("mods://not-a-file.ldtk");
+        assert_eq!(level_packs.active().name, "not-a-file");
+    }
+}
@@ -0,0 +1,484 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::color::ColorToPacked;
+use bevy::image::BevyDefault;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_ecs_ldtk::ldtk::{
+    loaded_level::LoadedLevel, EntityInstance, FieldValue, LayerInstance, TileInstance, Type,
+};
+use bevy_ecs_ldtk::prelude::{IntGridCell, LdtkFields, LdtkProject};
+
+use crate::level::crystal::{crystal_color, is_crystal_active};
+use crate::light::LightColor;
+use crate::settings::{SettingName, Settings};
+
+const TERRAIN_LAYER_IDENT: &str = "Terrain";
+const ENTITY_LAYER_IDENT: &str = "Entities";
+const SENSOR_COLOR_IDENT: &str = "toggle_color";
+
+/// The [`SettingName::PreviewScale`] used if the setting can't be read.
+const DEFAULT_PREVIEW_SCALE: u32 = 8;
+
+/// The int-grid values of the Terrain layer that light can't pass through: walls and active
+/// crystals.
+const LIGHT_BLOCKING_CELLS: [i32; 5] = [1, 3, 5, 7, 9];
+const MIRROR_CELL: i32 = 16;
+/// The int-grid values of the Terrain layer that are crystals, see
+/// [`crystal_color`].
+const CRYSTAL_CELLS: std::ops::RangeInclusive<i32> = 3..=10;
+/// How much active crystals and mirrors are tinted over their tiles.
+const CELL_OVERLAY_ALPHA: u8 = 140;
+const MIRROR_OVERLAY_COLOR: [u8; 4] = [200, 200, 200, CELL_OVERLAY_ALPHA];
+/// Stops beams that bounce between mirrors forever.
+const MAX_BEAM_STEPS: usize = 4096;
+
+// [R, G, B, A] colors for level previews of levels whose tileset hasn't loaded
+const LEVEL_PREVIEW_COLORS: [[u8; 4]; 17] = [
+    [0, 0, 0, 255],       // intgrid 0
+    [41, 54, 78, 255],    // intgrid 1
+    [117, 158, 202, 255], // intgrid 2
+    [255, 143, 212, 255], // intgrid 3
+    [128, 0, 64, 255],    // intgrid 4
+    [255, 0, 0, 255],     // intgrid 5
+    [80, 20, 15, 255],    // intgrid 6
+    [192, 203, 220, 255], // intgrid 7
+    [55, 58, 62, 255],    // intgrid 8
+    [80, 150, 230, 255],  // intgrid 9
+    [43, 85, 136, 255],   // intgrid 10
+    [0, 0, 0, 255],       // intgrid 11
+    [0, 0, 0, 255],       // intgrid 12
+    [0, 0, 0, 255],       // intgrid 13
+    [0, 0, 0, 255],       // intgrid 14
+    [115, 62, 57, 255],   // intgrid 15
+    [200, 200, 200, 255], // intgrid 16
+];
+
+fn sensor_color_to_rgba(sensor_color: &str) -> [u8; 4] {
+    match sensor_color {
+        "Pink" => [255, 143, 212, 255],
+        "Red" => [255, 0, 0, 255],
+        "White" => [229, 229, 229, 255],
+        "Blue" => [143, 225, 255, 255],
+        _ => [0, 0, 0, 255],
+    }
+}
+
+fn color_to_rgba(color: Color) -> [u8; 4] {
+    color.to_srgba().to_u8_array()
+}
+
+/// The number of preview pixels per level cell, from the [`Settings`].
+pub fn preview_scale(settings: &Settings) -> u32 {
+    settings
+        .choice(SettingName::PreviewScale)
+        .and_then(|choice| choice.parse().ok())
+        .unwrap_or(DEFAULT_PREVIEW_SCALE)
+}
+
+/// RGBA image that is drawn to in level cells, `scale` pixels per cell.
+struct PreviewCanvas {
+    width: u32,
+    height: u32,
+    scale: f32,
+    data: Vec<u8>,
+}
+
+impl PreviewCanvas {
+    fn new(c_wid: u32, c_hei: u32, scale: u32) -> Self {
+        let (width, height) = (c_wid * scale, c_hei * scale);
+        Self {
+            width,
+            height,
+            scale: scale as f32,
+            data: [0, 0, 0, 255].repeat((width * height) as usize),
+        }
+    }
+
+    fn blend_pixel(&mut self, x: i32, y: i32, color: [u8; 4], alpha: f32) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        let alpha = color[3] as f32 / 255.0 * alpha;
+        for (dst, src) in self.data[index..index + 3].iter_mut().zip(color) {
+            *dst = (src as f32 * alpha + *dst as f32 * (1.0 - alpha)).round() as u8;
+        }
+    }
+
+    /// The pixel range covered by `min..max` in level cells, at least one pixel wide.
+    fn pixel_range(&self, min: f32, max: f32) -> std::ops::Range<i32> {
+        let start = (min * self.scale).round() as i32;
+        let end = ((max * self.scale).round() as i32).max(start + 1);
+        start..end
+    }
+
+    fn fill_rect(&mut self, min: Vec2, max: Vec2, color: [u8; 4]) {
+        for y in self.pixel_range(min.y, max.y) {
+            for x in self.pixel_range(min.x, max.x) {
+                self.blend_pixel(x, y, color, 1.0);
+            }
+        }
+    }
+
+    fn draw_line(&mut self, from: Vec2, to: Vec2, color: [u8; 4]) {
+        let thickness = (self.scale / 4.0).max(1.0) as i32;
+        let steps = ((to - from).abs().max_element() * self.scale).ceil() as usize + 1;
+        for step in 0..=steps {
+            let point = from.lerp(to, step as f32 / steps as f32) * self.scale;
+            let corner = point.round().as_ivec2() - thickness / 2;
+            for dy in 0..thickness {
+                for dx in 0..thickness {
+                    self.blend_pixel(corner.x + dx, corner.y + dy, color, 1.0);
+                }
+            }
+        }
+    }
+
+    /// Draws a tile of an LDtk layer from its tileset, sampling the nearest tileset pixel.
+    fn draw_tile(&mut self, tileset: &Image, tile: &TileInstance, layer: &LayerInstance) {
+        let tile_size = layer.grid_size;
+        let grid_size = layer.grid_size as f32;
+        let min = (tile.px + IVec2::new(layer.px_total_offset_x, layer.px_total_offset_y))
+            .as_vec2()
+            / grid_size;
+        let max = min + Vec2::splat(tile_size as f32 / grid_size);
+        let (xs, ys) = (
+            self.pixel_range(min.x, max.x),
+            self.pixel_range(min.y, max.y),
+        );
+        let (tile_wid, tile_hei) = (xs.len() as f32, ys.len() as f32);
+        let tileset_wid = tileset.width() as i32;
+        for y in ys.clone() {
+            let mut v = ((y - ys.start) as f32 / tile_hei * tile_size as f32) as i32;
+            if tile.f & 2 != 0 {
+                v = tile_size - 1 - v;
+            }
+            for x in xs.clone() {
+                let mut u = ((x - xs.start) as f32 / tile_wid * tile_size as f32) as i32;
+                if tile.f & 1 != 0 {
+                    u = tile_size - 1 - u;
+                }
+                let (src_x, src_y) = (tile.src.x + u, tile.src.y + v);
+                if src_x >= tileset_wid || src_y >= tileset.height() as i32 {
+                    continue;
+                }
+                let index = (src_y * tileset_wid + src_x) as usize * 4;
+                let Some(color) = tileset.data.get(index..index + 4) else {
+                    continue;
+                };
+                let color = [color[0], color[1], color[2], color[3]];
+                self.blend_pixel(x, y, color, tile.a * layer.opacity);
+            }
+        }
+    }
+
+    fn into_image(self) -> Image {
+        Image::new(
+            Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            self.data,
+            TextureFormat::bevy_default(),
+            RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+        )
+    }
+}
+
+/// The tileset of an LDtk layer, if it has loaded. Only 8-bit RGBA tilesets can be drawn.
+fn layer_tileset<'a>(
+    layer: &LayerInstance,
+    ldtk_project: &LdtkProject,
+    images: &'a Assets<Image>,
+) -> Option<&'a Image> {
+    let handle = ldtk_project.tileset_map().get(&layer.tileset_def_uid?)?;
+    images.get(handle).filter(|tileset| {
+        matches!(
+            tileset.texture_descriptor.format,
+            TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm
+        )
+    })
+}
+
+/// Whether the tileset of an LDtk layer is still loading, so a preview drawn now won't show it.
+fn tileset_loading(
+    layer: &LayerInstance,
+    ldtk_project: &LdtkProject,
+    images: &Assets<Image>,
+) -> bool {
+    layer
+        .tileset_def_uid
+        .and_then(|uid| ldtk_project.tileset_map().get(&uid))
+        .is_some_and(|handle| images.get(handle).is_none())
+}
+
+/// A level preview made by [`render_level_preview`].
+pub struct RenderedLevelPreview {
+    pub image: Image,
+    /// Whether a tileset hadn't loaded yet, so the preview should be rendered again later.
+    pub incomplete: bool,
+}
+
+/// The top left and bottom right corner of an entity in level cells.
+fn entity_rect(entity: &EntityInstance, grid_size: f32) -> (Vec2, Vec2) {
+    let size = Vec2::new(entity.width as f32, entity.height as f32);
+    let min = entity.px.as_vec2() - entity.pivot * size;
+    (min / grid_size, (min + size) / grid_size)
+}
+
+/// Follows a light beam through the Terrain layer from `start` in direction `dir`, both in level
+/// cells, until it hits a wall or an active crystal or leaves the level. Beams are reflected by
+/// mirrors. Returns the points where the beam starts, bounces and stops.
+fn trace_beam(start: Vec2, dir: Vec2, cell_value: impl Fn(IVec2) -> Option<i32>) -> Vec<Vec2> {
+    let mut points = vec![start];
+    let mut dir = dir.normalize_or_zero();
+    if dir == Vec2::ZERO {
+        return points;
+    }
+    let mut pos = start;
+    let mut cell = start.floor().as_ivec2();
+    for _ in 0..MAX_BEAM_STEPS {
+        let next_x = (if dir.x > 0.0 { cell.x + 1 } else { cell.x }) as f32;
+        let next_y = (if dir.y > 0.0 { cell.y + 1 } else { cell.y }) as f32;
+        let t_x = if dir.x != 0.0 {
+            (next_x - pos.x) / dir.x
+        } else {
+            f32::INFINITY
+        };
+        let t_y = if dir.y != 0.0 {
+            (next_y - pos.y) / dir.y
+        } else {
+            f32::INFINITY
+        };
+        let crosses_x = t_x < t_y;
+        pos += dir * t_x.min(t_y);
+        let next_cell = if crosses_x {
+            cell + IVec2::new(dir.x.signum() as i32, 0)
+        } else {
+            cell + IVec2::new(0, dir.y.signum() as i32)
+        };
+        match cell_value(next_cell) {
+            None => break,
+            Some(value) if LIGHT_BLOCKING_CELLS.contains(&value) => break,
+            Some(MIRROR_CELL) => {
+                points.push(pos);
+                if crosses_x {
+                    dir.x = -dir.x;
+                } else {
+                    dir.y = -dir.y;
+                }
+            }
+            Some(_) => cell = next_cell,
+        }
+    }
+    points.push(pos);
+    points
+}
+
+/// Renders a preview of a level for the level select, `scale` pixels per level cell. The layers
+/// are drawn from their tilesets so the preview looks like the game, falling back to flat colors
+/// for the Terrain layer if its tileset hasn't loaded, which marks the preview as incomplete. On
+/// top of that, active crystals and mirrors are tinted, and moving platforms and their paths, the
+/// initial beams of light sources, sensors, shards and the start and end markers are drawn.
+pub fn render_level_preview(
+    level: LoadedLevel,
+    ldtk_project: &LdtkProject,
+    images: &Assets<Image>,
+    scale: u32,
+) -> Option<RenderedLevelPreview> {
+    let layers = level.layer_instances();
+    let terrain = layers
+        .iter()
+        .find(|layer| layer.identifier == TERRAIN_LAYER_IDENT)?;
+    let grid_size = terrain.grid_size as f32;
+    let mut canvas = PreviewCanvas::new(terrain.c_wid as u32, terrain.c_hei as u32, scale.max(1));
+    let mut incomplete = false;
+
+    // LDtk lists layers from top to bottom
+    for layer in layers.iter().rev() {
+        if !layer.visible || layer.layer_instance_type == Type::Entities {
+            continue;
+        }
+        incomplete |= tileset_loading(layer, ldtk_project, images);
+        match layer_tileset(layer, ldtk_project, images) {
+            Some(tileset) => {
+                for tile in layer.grid_tiles.iter().chain(&layer.auto_layer_tiles) {
+                    canvas.draw_tile(tileset, tile, layer);
+                }
+            }
+            None if layer.identifier == TERRAIN_LAYER_IDENT => {
+                for (i, value) in layer.int_grid_csv.iter().enumerate() {
+                    let Some(&color) = LEVEL_PREVIEW_COLORS.get(*value as usize) else {
+                        continue;
+                    };
+                    let cell = Vec2::new(
+                        (i as i32 % layer.c_wid) as f32,
+                        (i as i32 / layer.c_wid) as f32,
+                    );
+                    canvas.fill_rect(cell, cell + Vec2::ONE, color);
+                }
+            }
+            None => {}
+        }
+    }
+
+    // active crystals and mirrors decide where beams go, so they are tinted even over the tileset
+    for (i, value) in terrain.int_grid_csv.iter().enumerate() {
+        let color = match *value {
+            MIRROR_CELL => MIRROR_OVERLAY_COLOR,
+            value if CRYSTAL_CELLS.contains(&value) => {
+                let cell = IntGridCell { value };
+                if !is_crystal_active(cell) {
+                    continue;
+                }
+                let [r, g, b, _] = color_to_rgba(crystal_color(cell).button_color());
+                [r, g, b, CELL_OVERLAY_ALPHA]
+            }
+            _ => continue,
+        };
+        let cell = Vec2::new(
+            (i as i32 % terrain.c_wid) as f32,
+            (i as i32 / terrain.c_wid) as f32,
+        );
+        canvas.fill_rect(cell, cell + Vec2::ONE, color);
+    }
+
+    let Some(entities) = layers
+        .iter()
+        .find(|layer| layer.identifier == ENTITY_LAYER_IDENT)
+    else {
+        return Some(RenderedLevelPreview {
+            image: canvas.into_image(),
+            incomplete,
+        });
+    };
+    let terrain_cell = |cell: IVec2| {
+        let in_level = (0..terrain.c_wid).contains(&cell.x) && (0..terrain.c_hei).contains(&cell.y);
+        in_level.then(|| terrain.int_grid_csv[(cell.y * terrain.c_wid + cell.x) as usize])
+    };
+
+    for entity in entities.entity_instances.iter() {
+        let color = color_to_rgba(entity.smart_color);
+        let (min, max) = entity_rect(entity, grid_size);
+        match entity.identifier.as_str() {
+            "MovingPlatform" => {
+                let Ok(FieldValue::Points(path)) = entity
+                    .get_field_instance("path")
+                    .map(|field_instance| &field_instance.value)
+                else {
+                    continue;
+                };
+                // the points of the path are the bottom left cell of the platform, see
+                // `MovingPlatform`. Curved segments are drawn as straight lines
+                let size = max - min;
+                let path_color = [color[0], color[1], color[2], 160];
+                let mut last = (min + max) / 2.0;
+                for point in path.iter().flatten() {
+                    let center = point.as_vec2() + Vec2::new(0.0, 1.0 - size.y) + size / 2.0;
+                    canvas.draw_line(last, center, path_color);
+                    last = center;
+                }
+                canvas.fill_rect(min, max, color);
+            }
+            "LightSource" => {
+                let (Ok(direction), Ok(x_offset), Ok(y_offset)) = (
+                    entity.get_point_field("Direction"),
+                    entity.get_float_field("XOffset"),
+                    entity.get_float_field("YOffset"),
+                ) else {
+                    continue;
+                };
+                let both_directions = entity
+                    .get_bool_field("BothDirections")
+                    .is_ok_and(|both_directions| *both_directions);
+                // matches the beams shot in `spawn_level_light_beams`
+                let position = entity.grid + IVec2::new(0, entity.height / terrain.grid_size - 1);
+                let dir =
+                    (*direction - position).as_vec2() + Vec2::new(*x_offset, *y_offset) / grid_size;
+                let start = (min + max) / 2.0 + Vec2::new(*x_offset, -*y_offset) / grid_size;
+                let dirs = if both_directions {
+                    vec![dir, -dir]
+                } else {
+                    vec![dir]
+                };
+                // beams are drawn in the color of the light source in the editor, as the black
+                // light they shoot is hard to see in the preview
+                for dir in dirs {
+                    let beam = trace_beam(start, dir, terrain_cell);
+                    for segment in beam.windows(2) {
+                        canvas.draw_line(segment[0], segment[1], color);
+                    }
+                }
+                canvas.fill_rect(min, max, color);
+            }
+            "Sensor" => {
+                let color = entity
+                    .get_enum_field(SENSOR_COLOR_IDENT)
+                    .map_or(color, |sensor_color| sensor_color_to_rgba(sensor_color));
+                canvas.fill_rect(min, max, color);
+            }
+            "CrystalShard" => {
+                let color = entity
+                    .get_enum_field("light_color")
+                    .ok()
+                    .and_then(|light_color| LightColor::try_from(light_color).ok())
+                    .map_or(color, |light_color| {
+                        color_to_rgba(light_color.indicator_color())
+                    });
+                canvas.fill_rect(min, max, color);
+            }
+            "Start" | "StartMarker" | "EndMarker" => canvas.fill_rect(min, max, color),
+            _ => {}
+        }
+    }
+    Some(RenderedLevelPreview {
+        image: canvas.into_image(),
+        incomplete,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell_value<'a>(cells: &'a [&'a str]) -> impl Fn(IVec2) -> Option<i32> + 'a {
+        move |cell| {
+            let row = cells.get(usize::try_from(cell.y).ok()?)?;
+            match row.as_bytes().get(usize::try_from(cell.x).ok()?)? {
+                b'#' => Some(1),
+                b'M' => Some(MIRROR_CELL),
+                _ => Some(0),
+            }
+        }
+    }
+
+    #[test]
+    fn beam_stops_at_walls() {
+        let cells = ["....#"];
+        let beam = trace_beam(Vec2::new(0.5, 0.5), Vec2::X, cell_value(&cells));
+        assert_eq!(beam, vec![Vec2::new(0.5, 0.5), Vec2::new(4.0, 0.5)]);
+    }
+
+    #[test]
+    fn beam_is_reflected_by_mirrors() {
+        let cells = ["#..M"];
+        let beam = trace_beam(Vec2::new(1.5, 0.5), Vec2::X, cell_value(&cells));
+        assert_eq!(
+            beam,
+            vec![
+                Vec2::new(1.5, 0.5),
+                Vec2::new(3.0, 0.5),
+                Vec2::new(1.0, 0.5)
+            ]
+        );
+    }
+
+    #[test]
+    fn beam_stops_at_the_level_edge() {
+        let cells = ["....", "...."];
+        let beam = trace_beam(Vec2::new(0.5, 0.5), Vec2::new(1.0, 1.0), cell_value(&cells));
+        assert_eq!(beam, vec![Vec2::new(0.5, 0.5), Vec2::new(2.0, 2.0)]);
+    }
+}
@@ -1,64 +1,36 @@
 use std::collections::HashMap;
 
-use bevy::asset::RenderAssetUsages;
-use bevy::image::{BevyDefault, TextureFormatPixelInfo};
 use bevy::input::common_conditions::input_just_pressed;
 use bevy::prelude::*;
-use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
-use bevy_ecs_ldtk::ldtk::{FieldValue, Type};
+use bevy_ecs_ldtk::assets::LdtkExternalLevel;
+use bevy_ecs_ldtk::ldtk::loaded_level::LoadedLevel;
 use bevy_ecs_ldtk::prelude::LdtkFields;
 use bevy_ecs_ldtk::LevelIid;
 use bevy_ecs_ldtk::{prelude::LdtkProject, LdtkProjectHandle};
+use itertools::Itertools;
 
 use crate::camera::{
     camera_position_from_level, handle_move_camera, CameraControlType, CameraMoveEvent,
 };
+use crate::cli::CliArgs;
 use crate::config::Config;
-use crate::level::start_flag::StartFlag;
-use crate::level::{get_ldtk_level_data, level_box_from_level, CurrentLevel};
+use crate::input::action::InputAction;
+use crate::level::metadata::{LevelMetadata, LevelMetadataCache};
+use crate::level::start_flag::{start_flag_position, StartFlag};
+use crate::level::{
+    get_ldtk_level_data, get_loaded_level, level_box_from_level, CurrentLevel, LevelSystems,
+};
+use crate::level_pack::LevelPacks;
+use crate::level_preview::{preview_scale, render_level_preview};
 use crate::player::PlayerMarker;
-use crate::shared::{GameState, UiState, LYRA_RESPAWN_EPSILON};
+use crate::save::SaveData;
+use crate::settings::Settings;
+use crate::shared::{GameState, UiState};
 use crate::sound::{BgmTrack, ChangeBgmEvent};
+use crate::speedrun::format_ticks;
 
 pub struct LevelSelectPlugin;
 
-const START_FLAG_IDENT: &str = "Start";
-const TERRAIN_LAYER_IDENT: &str = "Terrain";
-const ENTITY_LAYER_IDENT: &str = "Entities";
-const SENSOR_ENTITY_IDENT: &str = "Sensor";
-const SENSOR_COLOR_IDENT: &str = "toggle_color";
-
-// [R, G, B, A] colors for level preview
-const LEVEL_PREVIEW_COLORS: [[u8; 4]; 17] = [
-    [0, 0, 0, 255],       // intgrid 0
-    [41, 54, 78, 255],    // intgrid 1
-    [117, 158, 202, 255], // intgrid 2
-    [255, 143, 212, 255], // intgrid 3
-    [128, 0, 64, 255],    // intgrid 4
-    [255, 0, 0, 255],     // intgrid 5
-    [80, 20, 15, 255],    // intgrid 6
-    [192, 203, 220, 255], // intgrid 7
-    [55, 58, 62, 255],    // intgrid 8
-    [80, 150, 230, 255],  // intgrid 9
-    [43, 85, 136, 255],   // intgrid 10
-    [0, 0, 0, 255],       // intgrid 11
-    [0, 0, 0, 255],       // intgrid 12
-    [0, 0, 0, 255],       // intgrid 13
-    [0, 0, 0, 255],       // intgrid 14
-    [115, 62, 57, 255],   // intgrid 15
-    [200, 200, 200, 255], // intgrid 16
-];
-
-fn sensor_color_to_rgba(sensor_color: &str) -> [u8; 4] {
-    match sensor_color {
-        "Pink" => [255, 143, 212, 255],
-        "Red" => [255, 0, 0, 255],
-        "White" => [229, 229, 229, 255],
-        "Blue" => [143, 225, 255, 255],
-        _ => [0, 0, 0, 255],
-    }
-}
-
 #[derive(Component)]
 struct LevelSelectUiMarker;
 
@@ -68,8 +40,21 @@ pub struct LevelPreviewMarker;
 #[derive(Component)]
 pub struct LevelPreviewLockedMarker;
 
+/// A rendered level preview, see [`render_level_preview`].
+#[derive(Clone)]
+pub struct LevelPreview {
+    /// The [`preview_scale`] it was rendered at.
+    scale: u32,
+    /// Whether it was rendered before every tileset loaded, see
+    /// [`RenderedLevelPreview`](crate::level_preview::RenderedLevelPreview).
+    incomplete: bool,
+    aspect_ratio: f32,
+    image: Handle<Image>,
+}
+
+/// [`Resource`] caching the rendered [`LevelPreview`] of every hovered level.
 #[derive(Resource)]
-pub struct LevelPreviewStore(HashMap<String, (Vec2, Handle<Image>)>);
+pub struct LevelPreviewStore(HashMap<LevelIid, LevelPreview>);
 
 // FIXME .0 is ldtk level index, .1 is index into the Levels.0 vector
 #[derive(Component)]
@@ -77,7 +62,7 @@ pub struct LevelSelectButtonIndex(usize, usize);
 
 #[derive(PartialEq, Eq)]
 pub struct LevelSaveData {
-    level_id: String,
+    pub level_id: String,
     pub level_iid: LevelIid,
     level_index: usize,
     pub complete: bool,
@@ -104,6 +89,8 @@ fn init_levels(
     query_ldtk: Query<&LdtkProjectHandle>,
     ldtk_assets: Res<Assets<LdtkProject>>,
     config: Res<Config>,
+    save_data: Res<SaveData>,
+    level_packs: Res<LevelPacks>,
 ) {
     if !res_levels.0.is_empty() {
         return;
@@ -126,12 +113,18 @@ fn init_levels(
         if &level_id[0..1] == "." {
             continue;
         }
+        let progress = save_data
+            .levels
+            .get(&level_packs.active().path)
+            .and_then(|pack_progress| pack_progress.get(&level.iid));
         res_levels.0.push(LevelSaveData {
             level_id: level_id.to_string(),
             level_iid: LevelIid::new(level.iid.clone()),
             level_index: i,
-            complete: config.debug_config.unlock_levels,
-            locked: !config.debug_config.unlock_levels,
+            complete: config.debug_config.unlock_levels
+                || progress.is_some_and(|progress| progress.complete),
+            locked: !config.debug_config.unlock_levels
+                && progress.is_none_or(|progress| progress.locked),
         });
     }
     res_levels.0.sort();
@@ -140,11 +133,28 @@ fn init_levels(
 
 impl Plugin for LevelSelectPlugin {
     fn build(&self, app: &mut App) {
+        if let Some(level_id) = app
+            .world()
+            .get_resource::<CliArgs>()
+            .and_then(|args| args.level.clone())
+        {
+            app.insert_resource(StartLevel(level_id)).add_systems(
+                Update,
+                start_at_level
+                    .run_if(in_state(GameState::Ui))
+                    .run_if(resource_exists::<StartLevel>),
+            );
+        }
+
         app.insert_resource(LevelPreviewStore(HashMap::new()))
             .insert_resource(Levels(Vec::new()))
+            .add_systems(
+                PreUpdate,
+                clear_level_select_cache.in_set(LevelSystems::Reload),
+            )
             .add_systems(
                 PostUpdate,
-                switch_to_level_select.run_if(input_just_pressed(KeyCode::KeyL)),
+                switch_to_level_select.run_if(input_just_pressed(InputAction::LevelSelect)),
             )
             .add_systems(
                 FixedUpdate,
@@ -160,6 +170,22 @@ impl Plugin for LevelSelectPlugin {
     }
 }
 
+/// [`System`] that clears the [`Levels`] and the level previews when the LDtk project is reloaded,
+/// so that they are rebuilt from the new levels.
+fn clear_level_select_cache(
+    mut commands: Commands,
+    mut levels: ResMut<Levels>,
+    mut level_preview_store: ResMut<LevelPreviewStore>,
+    q_level_select: Query<Entity, With<LevelSelectUiMarker>>,
+) {
+    levels.0.clear();
+    level_preview_store.0.clear();
+    // respawned by spawn_level_select if it is open
+    for entity in q_level_select.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 fn switch_to_level_select(
     mut next_ui_state: ResMut<NextState<UiState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
@@ -168,14 +194,20 @@ fn switch_to_level_select(
     next_ui_state.set(UiState::LevelSelect);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_level_select(
     mut commands: Commands,
     level_select_ui_query: Query<Entity, With<LevelSelectUiMarker>>,
     asset_server: Res<AssetServer>,
     mut ev_change_bgm: EventWriter<ChangeBgmEvent>,
     sorted_levels: Res<Levels>,
+    save_data: Res<SaveData>,
+    level_packs: Res<LevelPacks>,
+    fixed_time: Res<Time<Fixed>>,
+    level_metadata: Res<LevelMetadataCache>,
 ) {
-    if level_select_ui_query.get_single().is_ok() {
+    // wait for init_levels, e.g. while a new level pack is loading
+    if level_select_ui_query.get_single().is_ok() || sorted_levels.0.is_empty() {
         return;
     }
     let font = TextFont {
@@ -185,6 +217,32 @@ fn spawn_level_select(
 
     ev_change_bgm.send(ChangeBgmEvent(BgmTrack::LevelSelect));
 
+    // levels stay sorted by LevelId within a chapter, and levels without a chapter go last
+    let chapter_of = |level: &LevelSaveData| {
+        level_metadata
+            .get(&level.level_iid)
+            .and_then(|metadata| metadata.chapter)
+    };
+    let mut levels: Vec<_> = sorted_levels.0.iter().enumerate().collect();
+    levels.sort_by_key(|(_, level)| {
+        let chapter = chapter_of(level);
+        (chapter.is_none(), chapter)
+    });
+    let chapters: Vec<_> = levels
+        .into_iter()
+        .chunk_by(|(_, level)| chapter_of(level))
+        .into_iter()
+        .map(|(_, levels)| {
+            let levels: Vec<_> = levels.collect();
+            let title = levels
+                .iter()
+                .filter_map(|(_, level)| level_metadata.get(&level.level_iid))
+                .max_by_key(|metadata| metadata.chapter_name.is_some())
+                .and_then(LevelMetadata::chapter_title);
+            (title, levels)
+        })
+        .collect();
+
     commands
         .spawn((
             LevelSelectUiMarker,
@@ -207,54 +265,83 @@ fn spawn_level_select(
                     width: Val::Percent(100.),
                     padding: UiRect::all(Val::Px(16.0)),
                     height: Val::Auto,
-                    flex_direction: FlexDirection::Row,
-                    flex_wrap: FlexWrap::Wrap,
-                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
                     align_items: AlignItems::Center,
                     ..default()
                 })
                 .with_children(|parent| {
-                    for (
-                        i,
-                        LevelSaveData {
-                            level_id,
-                            level_iid: _,
-                            level_index: index,
-                            complete,
-                            locked,
-                        },
-                    ) in sorted_levels.0.iter().enumerate()
-                    {
+                    for (title, levels) in chapters {
+                        if let Some(title) = title {
+                            parent.spawn((Text::new(title), font.clone().with_font_size(24.)));
+                        }
                         parent
-                            .spawn((
-                                Button,
-                                Node {
-                                    width: Val::Px(96.0),
-                                    height: Val::Px(96.0),
-                                    padding: UiRect::all(Val::Px(8.0)),
-                                    margin: UiRect::all(Val::Px(4.0)),
-                                    border: UiRect::all(Val::Px(2.0)),
-                                    justify_content: JustifyContent::Center,
-                                    align_items: AlignItems::Center,
-                                    ..default()
-                                },
-                                BorderColor(if *complete {
-                                    Color::srgb(0.0, 1.0, 0.0)
-                                } else if !*locked {
-                                    Color::WHITE
-                                } else {
-                                    Color::srgb(1.0, 0.0, 0.0)
-                                }),
-                                LevelSelectButtonIndex(*index, i),
-                            ))
-                            .with_child((
-                                if *locked {
-                                    Text::new("LOCKED")
-                                } else {
-                                    Text::new(level_id.to_string())
-                                },
-                                font.clone().with_font_size(24.),
-                            ));
+                            .spawn(Node {
+                                width: Val::Percent(100.),
+                                flex_direction: FlexDirection::Row,
+                                flex_wrap: FlexWrap::Wrap,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                for (
+                                    i,
+                                    LevelSaveData {
+                                        level_id,
+                                        level_iid,
+                                        level_index: index,
+                                        complete,
+                                        locked,
+                                    },
+                                ) in levels
+                                {
+                                    parent
+                                        .spawn((
+                                            Button,
+                                            Node {
+                                                width: Val::Px(96.0),
+                                                height: Val::Px(96.0),
+                                                padding: UiRect::all(Val::Px(8.0)),
+                                                margin: UiRect::all(Val::Px(4.0)),
+                                                border: UiRect::all(Val::Px(2.0)),
+                                                flex_direction: FlexDirection::Column,
+                                                justify_content: JustifyContent::Center,
+                                                align_items: AlignItems::Center,
+                                                ..default()
+                                            },
+                                            BorderColor(if *complete {
+                                                Color::srgb(0.0, 1.0, 0.0)
+                                            } else if !*locked {
+                                                Color::WHITE
+                                            } else {
+                                                Color::srgb(1.0, 0.0, 0.0)
+                                            }),
+                                            LevelSelectButtonIndex(*index, i),
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn((
+                                                if *locked {
+                                                    Text::new("LOCKED")
+                                                } else {
+                                                    Text::new(level_id.to_string())
+                                                },
+                                                font.clone().with_font_size(24.),
+                                            ));
+                                            if let Some(best) = save_data.personal_best(
+                                                &level_packs.active().path,
+                                                level_iid,
+                                            ) {
+                                                parent.spawn((
+                                                    Text::new(format_ticks(
+                                                        best,
+                                                        fixed_time.timestep(),
+                                                    )),
+                                                    font.clone().with_font_size(16.),
+                                                ));
+                                            }
+                                        });
+                                }
+                            });
                     }
                 });
             parent
@@ -301,6 +388,83 @@ fn despawn_level_select(
     commands.entity(entity).despawn_recursive();
 }
 
+/// Moves the player to the `Start` flag of a level, and the camera along with them.
+fn move_to_start_flag(
+    level: LoadedLevel,
+    player_transform: &mut Transform,
+    ev_move_camera: &mut EventWriter<CameraMoveEvent>,
+) {
+    let level_box = level_box_from_level(level.raw());
+    let Some(start_position) = start_flag_position(level) else {
+        return;
+    };
+    player_transform.translation.x = start_position.x;
+    player_transform.translation.y = start_position.y;
+
+    // Send a camera transition event to tp the camera immediately
+    ev_move_camera.send(CameraMoveEvent {
+        to: camera_position_from_level(level_box, start_position),
+        variant: CameraControlType::Instant,
+    });
+}
+
+/// [`Resource`] holding the `LevelId` passed with `--level`, until the player has been moved to
+/// that level.
+#[derive(Resource)]
+pub struct StartLevel(String);
+
+/// [`System`] that skips the menus and starts at the level passed with `--level`, once the level
+/// and the player have been loaded.
+#[allow(clippy::too_many_arguments)]
+fn start_at_level(
+    mut commands: Commands,
+    start_level: Res<StartLevel>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    external_levels: Res<Assets<LdtkExternalLevel>>,
+    query_ldtk: Query<&LdtkProjectHandle>,
+    mut query_player: Query<&mut Transform, With<PlayerMarker>>,
+    mut ev_move_camera: EventWriter<CameraMoveEvent>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    let Ok(ldtk_handle) = query_ldtk.get_single() else {
+        return;
+    };
+    let Some(ldtk_project) = ldtk_assets.get(ldtk_handle) else {
+        return;
+    };
+    let ldtk_levels = &ldtk_project.json_data().levels;
+    let Ok(mut player_transform) = query_player.get_single_mut() else {
+        return;
+    };
+
+    let Some(level) = ldtk_levels
+        .iter()
+        .find(|level| level.get_string_field("LevelId").ok() == Some(&start_level.0))
+    else {
+        let mut level_ids: Vec<_> = ldtk_levels
+            .iter()
+            .filter_map(|level| level.get_string_field("LevelId").ok())
+            .collect();
+        level_ids.sort();
+        error!(
+            "There is no level with LevelId {}, the levels are: {level_ids:?}",
+            start_level.0
+        );
+        commands.remove_resource::<StartLevel>();
+        return;
+    };
+    // wait for the level's file to load if the project uses separate level files
+    let Some(level) = get_loaded_level(ldtk_project, &external_levels, &level.iid) else {
+        return;
+    };
+    commands.remove_resource::<StartLevel>();
+    move_to_start_flag(level, &mut player_transform, &mut ev_move_camera);
+    next_game_state.set(GameState::Playing);
+    // don't trigger the camera transition, see handle_level_selection
+    current_level.level_iid = LevelIid::new("");
+}
+
 #[allow(clippy::type_complexity)]
 #[allow(clippy::too_many_arguments)]
 pub fn handle_level_selection(
@@ -310,6 +474,7 @@ pub fn handle_level_selection(
     >,
     mut next_game_state: ResMut<NextState<GameState>>,
     ldtk_assets: Res<Assets<LdtkProject>>,
+    external_levels: Res<Assets<LdtkExternalLevel>>,
     query_ldtk: Query<&LdtkProjectHandle>,
     mut query_player: Query<&mut Transform, (With<PlayerMarker>, Without<StartFlag>)>,
     mut ev_move_camera: EventWriter<CameraMoveEvent>,
@@ -326,57 +491,41 @@ pub fn handle_level_selection(
     >,
     mut commands: Commands,
     res_levels: Res<Levels>,
+    settings: Res<Settings>,
 ) {
     let Ok(ldtk_handle) = query_ldtk.get_single() else {
         return;
     };
-    let Ok(ldtk_levels) = get_ldtk_level_data(ldtk_assets.into_inner(), ldtk_handle) else {
+    let Some(ldtk_project) = ldtk_assets.get(ldtk_handle) else {
         return;
     };
+    let ldtk_levels = &ldtk_project.json_data().levels;
     'loop_interactions: for (interaction, index) in interaction_query.iter_mut() {
-        if index.0 >= ldtk_levels.len() {
-            panic!("Selected level index is out of bounds!")
+        if *interaction == Interaction::None {
+            continue;
         }
-        let level = &ldtk_levels[index.0];
+        // the project can be reloaded or switched while the level select is open, which leaves the
+        // buttons pointing at levels that may not exist anymore until they are rebuilt
+        let (Some(level), Some(locked)) = (
+            ldtk_levels.get(index.0),
+            res_levels.0.get(index.1).map(|level| level.locked),
+        ) else {
+            continue;
+        };
+        let Some(loaded_level) = get_loaded_level(ldtk_project, &external_levels, &level.iid)
+        else {
+            warn!("The file of level {} hasn't loaded yet", level.identifier);
+            continue;
+        };
         match *interaction {
             Interaction::Pressed => {
-                if res_levels.0[index.1].locked {
+                if locked {
                     return;
                 }
-                let Some(layers) = level.layer_instances.as_ref() else {
-                    panic!("Layers not found! (This is probably because you are using the \"Separate level files\" option.)")
+                let Ok(mut player_transform) = query_player.get_single_mut() else {
+                    return;
                 };
-                'loop_layers: for layer in layers {
-                    if layer.layer_instance_type == Type::Entities {
-                        for entity in &layer.entity_instances {
-                            if entity.identifier == START_FLAG_IDENT {
-                                let (Some(player_x), Some(player_y)) =
-                                    (entity.world_x, entity.world_y)
-                                else {
-                                    panic!("Start flag entity has no coordinates! (This is probably because your LDTK world is not in free layout mode.)");
-                                };
-                                let Ok(mut player_transform) = query_player.get_single_mut() else {
-                                    panic!("Could not find player!");
-                                };
-                                player_transform.translation.x = player_x as f32;
-                                player_transform.translation.y =
-                                    -player_y as f32 + LYRA_RESPAWN_EPSILON;
-
-                                // Send a camera transition event to tp the camera immediately
-                                let camera_pos = camera_position_from_level(
-                                    level_box_from_level(&ldtk_levels[index.0]),
-                                    player_transform.translation.xy(),
-                                );
-                                ev_move_camera.send(CameraMoveEvent {
-                                    to: camera_pos,
-                                    variant: CameraControlType::Instant,
-                                });
-
-                                break 'loop_layers;
-                            }
-                        }
-                    }
-                }
+                move_to_start_flag(loaded_level, &mut player_transform, &mut ev_move_camera);
 
                 next_game_state.set(GameState::Playing);
                 // Set the current level_iid to an empty string so we don't trigger the camera transition (skull emoji)
@@ -384,97 +533,39 @@ pub fn handle_level_selection(
                 break 'loop_interactions;
             }
             Interaction::Hovered => {
-                let level_id = level
-                    .get_string_field("LevelId")
-                    .expect("Levels should always have a level id!");
-                let (level_dims, level_preview) = match level_preview_store.0.get(level_id) {
-                    Some(level_preview) => level_preview.clone(),
-                    None => {
-                        let level_layers =
-                            level.layer_instances.as_ref().expect("Layers not found!");
-                        let Some((layer_w, layer_h, layer_data)) =
-                            level_layers.iter().find_map(|layer| {
-                                if layer.identifier == TERRAIN_LAYER_IDENT {
-                                    Some((
-                                        layer.c_wid as usize,
-                                        layer.c_hei as usize,
-                                        &layer.int_grid_csv,
-                                    ))
-                                } else {
-                                    None
-                                }
-                            })
+                let level_iid = LevelIid::new(level.iid.clone());
+                let scale = preview_scale(&settings);
+                let level_preview = match level_preview_store.0.get(&level_iid) {
+                    Some(level_preview)
+                        if level_preview.scale == scale && !level_preview.incomplete =>
+                    {
+                        level_preview.clone()
+                    }
+                    _ => {
+                        let Some(preview) =
+                            render_level_preview(loaded_level, ldtk_project, &assets, scale)
                         else {
-                            panic!("Terrain layer data not found!");
+                            warn!("Level {} has no Terrain layer to preview", level.identifier);
+                            continue;
                         };
-                        let Some(level_entities) = level_layers.iter().find_map(|layer| {
-                            if layer.identifier == ENTITY_LAYER_IDENT {
-                                Some(&layer.entity_instances)
-                            } else {
-                                None
-                            }
-                        }) else {
-                            panic!("Entity layer data not found!");
+                        let level_preview = LevelPreview {
+                            scale,
+                            incomplete: preview.incomplete,
+                            aspect_ratio: preview.image.width() as f32
+                                / preview.image.height() as f32,
+                            image: assets.add(preview.image),
                         };
-                        let mut level_preview_data = Vec::with_capacity(layer_w * layer_h);
-                        let pixel_size = TextureFormat::bevy_default().pixel_size();
-                        for tile in layer_data {
-                            for i in 0..pixel_size {
-                                level_preview_data.push(LEVEL_PREVIEW_COLORS[*tile as usize][i]);
-                            }
-                        }
-                        for entity in level_entities {
-                            if entity.identifier != SENSOR_ENTITY_IDENT {
-                                continue;
-                            }
-                            let entity_coords = entity.grid;
-                            let Some(entity_color) =
-                                entity.field_instances.iter().find_map(|instance| {
-                                    if instance.identifier == SENSOR_COLOR_IDENT {
-                                        let FieldValue::Enum(Some(ref color)) = instance.value
-                                        else {
-                                            panic!("Sensor color should be an enum!");
-                                        };
-                                        Some(color)
-                                    } else {
-                                        None
-                                    }
-                                })
-                            else {
-                                panic!("Could not find sensor color field!");
-                            };
-                            let rgba = sensor_color_to_rgba(entity_color);
-                            let image_data_index = (entity_coords.y as usize * layer_w
-                                + entity_coords.x as usize)
-                                * pixel_size;
-                            level_preview_data[image_data_index..(pixel_size + image_data_index)]
-                                .copy_from_slice(&rgba[..pixel_size]);
-                        }
-                        let preview = Image::new(
-                            Extent3d {
-                                width: layer_w as u32,
-                                height: layer_h as u32,
-                                depth_or_array_layers: 1,
-                            },
-                            TextureDimension::D2,
-                            level_preview_data,
-                            TextureFormat::bevy_default(),
-                            RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
-                        );
-                        let new_handle = assets.add(preview);
-                        let level_dims = Vec2::new(layer_w as f32, layer_h as f32);
                         level_preview_store
                             .0
-                            .insert(level_id.into(), (level_dims, new_handle.clone()));
-                        (level_dims, new_handle)
+                            .insert(level_iid, level_preview.clone());
+                        level_preview
                     }
                 };
                 let Ok((level_preview_entity, level_preview_nodes)) =
                     query_level_preview.get_single_mut()
                 else {
-                    panic!("Could not find level preview");
+                    return;
                 };
-                let locked = res_levels.0[index.1].locked;
                 const LOCKED_LEVEL_PREVIEW_SCALE: f32 = 0.3;
                 let scaled_color = Color::srgba(
                     LOCKED_LEVEL_PREVIEW_SCALE,
@@ -485,15 +576,15 @@ pub fn handle_level_selection(
                 if let Some((mut level_preview_image_node, mut level_preview_node)) =
                     level_preview_nodes
                 {
-                    level_preview_image_node.image = level_preview;
+                    level_preview_image_node.image = level_preview.image;
                     if locked {
                         level_preview_image_node.color = scaled_color
                     } else {
                         level_preview_image_node.color = Color::WHITE;
                     }
-                    level_preview_node.aspect_ratio = Some(level_dims.x / level_dims.y);
+                    level_preview_node.aspect_ratio = Some(level_preview.aspect_ratio);
                 } else {
-                    let mut image_node = ImageNode::new(level_preview);
+                    let mut image_node = ImageNode::new(level_preview.image);
                     if locked {
                         image_node.color = scaled_color;
                     } else {
@@ -505,7 +596,7 @@ pub fn handle_level_selection(
                             width: Val::Percent(60.),
                             height: Val::Auto,
                             max_height: Val::Percent(50.),
-                            aspect_ratio: Some(level_dims.x / level_dims.y),
+                            aspect_ratio: Some(level_preview.aspect_ratio),
                             ..default()
                         },
                     ));
@@ -520,7 +611,7 @@ pub fn handle_level_selection(
                     preview_locked_node.color = Color::srgba(1., 1., 1., 0.);
                 }
             }
-            _ => {}
+            Interaction::None => {}
         }
     }
 }
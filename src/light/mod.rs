@@ -7,11 +7,18 @@ use bevy_ecs_ldtk::prelude::*;
 use enum_map::Enum;
 use render::{LightMaterial, LightRenderData};
 use segments::{
-    cleanup_light_sources, simulate_light_sources, spawn_needed_segments, tick_light_sources,
-    visually_sync_segments, LightSegmentCache, PrevLightBeamPlayback,
+    cleanup_light_sources, clear_light_segments, simulate_light_sources, spawn_needed_segments,
+    tick_light_sources, visually_sync_segments, LightSegmentCache, PrevLightBeamPlayback,
 };
+use serde::{Deserialize, Serialize};
 
-use crate::{level::LevelSystems, lighting::LineLight2d};
+use crate::{
+    level::{
+        entity::{resolve_fallible, Fallible, LdtkEntityError},
+        LevelSystems,
+    },
+    lighting::LineLight2d,
+};
 
 mod render;
 pub mod segments;
@@ -49,10 +56,18 @@ impl Plugin for LightManagementPlugin {
             )
             // why does this need to be on update???
             .add_systems(Update, cleanup_light_sources.in_set(LevelSystems::Reset))
+            .add_systems(
+                PreUpdate,
+                (clear_light_segments, clear_level_light_beams).in_set(LevelSystems::Reload),
+            )
             .add_systems(
                 PostUpdate,
                 spawn_level_light_beams.in_set(LevelSystems::Simulation),
             )
+            .add_systems(
+                PreUpdate,
+                resolve_fallible::<LightBeamLDTKSource>.in_set(LevelSystems::Processing),
+            )
             .add_systems(
                 PostUpdate,
                 add_light_beam_added.in_set(LevelSystems::Processing),
@@ -72,6 +87,20 @@ fn add_light_beam_added(
     }
 }
 
+/// [`System`] that despawns the black [`LightBeamSource`]s spawned by [`spawn_level_light_beams`]
+/// when the LDtk project is reloaded, since they aren't children of the level and would otherwise
+/// be spawned a second time.
+fn clear_level_light_beams(
+    mut commands: Commands,
+    q_light_sources: Query<(Entity, &LightBeamSource)>,
+) {
+    for (entity, light_beam_source) in q_light_sources.iter() {
+        if light_beam_source.color == LightColor::Black {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
 fn spawn_level_light_beams(
     mut commands: Commands,
     ldtk_sources: Query<
@@ -155,7 +184,7 @@ pub struct LightSourceZBundle {
 #[derive(Bundle, LdtkEntity)]
 pub struct LightSourceBundle {
     #[from_entity_instance]
-    pub beam_source: LightBeamLDTKSource,
+    pub beam_source: Fallible<LightBeamLDTKSource>,
 }
 
 // Component for LDTK Light Source
@@ -168,24 +197,26 @@ pub struct LightBeamLDTKSource {
     pub both_directions: bool,
 }
 
-impl From<&bevy_ecs_ldtk::EntityInstance> for LightBeamLDTKSource {
-    fn from(entity_instance: &bevy_ecs_ldtk::EntityInstance) -> Self {
+impl TryFrom<&bevy_ecs_ldtk::EntityInstance> for LightBeamLDTKSource {
+    type Error = LdtkEntityError;
+
+    fn try_from(entity_instance: &bevy_ecs_ldtk::EntityInstance) -> Result<Self, Self::Error> {
         let height = entity_instance.height;
         let position = IVec2::new(
             entity_instance.grid.x,
             entity_instance.grid.y + (height / (BLOCK_WIDTH as i32)) - 1,
         );
-        let x_offset = *entity_instance.get_float_field("XOffset").unwrap();
-        let y_offset = *entity_instance.get_float_field("YOffset").unwrap();
-        let direction = *entity_instance.get_point_field("Direction").unwrap();
-        let both_directions = *entity_instance.get_bool_field("BothDirections").unwrap();
-        LightBeamLDTKSource {
+        let x_offset = *entity_instance.get_float_field("XOffset")?;
+        let y_offset = *entity_instance.get_float_field("YOffset")?;
+        let direction = *entity_instance.get_point_field("Direction")?;
+        let both_directions = *entity_instance.get_bool_field("BothDirections")?;
+        Ok(LightBeamLDTKSource {
             direction,
             position,
             x_offset,
             y_offset,
             both_directions,
-        }
+        })
     }
 }
 
@@ -201,7 +232,7 @@ pub struct LightSegmentZBundle {
 }
 
 /// [`Enum`] for each of the light colors.
-#[derive(Enum, Clone, Copy, Default, PartialEq, Debug, Eq, Hash)]
+#[derive(Enum, Clone, Copy, Default, PartialEq, Debug, Eq, Hash, Serialize, Deserialize)]
 pub enum LightColor {
     #[default]
     Green,
@@ -223,15 +254,19 @@ impl From<LightColor> for LightMaterial {
     }
 }
 
-impl From<&String> for LightColor {
-    fn from(value: &String) -> Self {
+impl TryFrom<&String> for LightColor {
+    type Error = LdtkEntityError;
+
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
         match value.as_str() {
-            "Purple" => LightColor::Purple,
-            "Green" => LightColor::Green,
-            "White" => LightColor::White,
-            "Blue" => LightColor::Blue,
-            "Black" => LightColor::Black,
-            _ => panic!("String {} does not represent Light Color", value),
+            "Purple" => Ok(LightColor::Purple),
+            "Green" => Ok(LightColor::Green),
+            "White" => Ok(LightColor::White),
+            "Blue" => Ok(LightColor::Blue),
+            "Black" => Ok(LightColor::Black),
+            _ => Err(LdtkEntityError::new(format!(
+                "String {value} does not represent Light Color"
+            ))),
         }
     }
 }
@@ -16,6 +16,8 @@ const LIGHT_SHADER_PATH: &str = "shaders/light.wgsl";
 pub struct LightRenderData {
     pub mesh: Mesh2d,
     pub material_map: EnumMap<LightColor, MeshMaterial2d<LightMaterial>>,
+    /// Materials used for segments that travel through [`Water`](crate::level::water::Water).
+    pub water_material_map: EnumMap<LightColor, MeshMaterial2d<LightMaterial>>,
 }
 
 impl FromWorld for LightRenderData {
@@ -37,6 +39,13 @@ impl FromWorld for LightRenderData {
                 LightColor::Blue => materials.add(LightMaterial::from(LightColor::Blue)).into(),
                 LightColor::Black => materials.add(LightMaterial::from(LightColor::Black)).into(),
             },
+            water_material_map: enum_map! {
+                LightColor::Green => materials.add(LightMaterial::underwater(LightColor::Green)).into(),
+                LightColor::Purple => materials.add(LightMaterial::underwater(LightColor::Purple)).into(),
+                LightColor::White => materials.add(LightMaterial::underwater(LightColor::White)).into(),
+                LightColor::Blue => materials.add(LightMaterial::underwater(LightColor::Blue)).into(),
+                LightColor::Black => materials.add(LightMaterial::underwater(LightColor::Black)).into(),
+            },
         }
     }
 }
@@ -52,6 +61,22 @@ pub struct LightMaterial {
     pub _wasm_padding: Vec2,
 }
 
+impl LightMaterial {
+    /// A [`LightMaterial`] for the given [`LightColor`], tinted towards the color of water.
+    pub fn underwater(light_color: LightColor) -> Self {
+        let water_tint = LinearRgba::rgb(0.4, 0.8, 1.2);
+        let color = LinearRgba::from(light_color.light_beam_color());
+        LightMaterial {
+            color: LinearRgba::rgb(
+                color.red * water_tint.red,
+                color.green * water_tint.green,
+                color.blue * water_tint.blue,
+            ),
+            ..LightMaterial::from(light_color)
+        }
+    }
+}
+
 impl Material2d for LightMaterial {
     fn fragment_shader() -> ShaderRef {
         LIGHT_SHADER_PATH.into()
@@ -7,7 +7,11 @@ use super::{
 };
 use crate::{
     camera::HIGHRES_LAYER,
-    level::{mirror::Mirror, sensor::LightSensor},
+    level::{
+        mirror::Mirror,
+        sensor::LightSensor,
+        water::{cast_ray_through_water, refract_through_water, Water},
+    },
     lighting::LineLight2d,
     particle::spark::SparkExplosionEvent,
     shared::GroupLabel,
@@ -102,6 +106,7 @@ pub fn play_light_beam(
     source: &LightBeamSource,
     black_ray_qry: &Query<(Entity, &BlackRayComponent)>,
     q_mirrors: &Query<&Mirror>,
+    q_water: &Query<(Entity, &Water, &GlobalTransform)>,
 ) -> LightBeamPlayback {
     let mut ray_pos = source.start_pos;
     let mut ray_dir = source.start_dir;
@@ -146,10 +151,35 @@ pub fn play_light_beam(
 
     let mut i = 0;
     let mut extra_bounces_from_mirror = 0;
-    while i < num_segments + extra_bounces_from_mirror && i < LIGHT_MAX_SEGMENTS {
-        let Some((entity, intersection)) =
-            rapier_context.cast_ray_and_get_normal(ray_pos, ray_dir, remaining_time, true, ray_qry)
-        else {
+    let mut extra_segments_from_water = 0;
+    while i < num_segments + extra_bounces_from_mirror + extra_segments_from_water
+        && i < LIGHT_MAX_SEGMENTS
+    {
+        let hit =
+            rapier_context.cast_ray_and_get_normal(ray_pos, ray_dir, remaining_time, true, ray_qry);
+
+        // crossing the surface of water bends the beam without using up a bounce
+        let max_toi = hit.map_or(remaining_time, |(_, x)| x.time_of_impact);
+        if let Some((water_entity, toi, normal, entering)) =
+            cast_ray_through_water(q_water, ray_pos, ray_dir, max_toi)
+        {
+            playback.elapsed_time += toi;
+            remaining_time -= toi;
+            ray_pos += ray_dir * toi;
+
+            playback.intersections.push(LightBeamIntersection {
+                entity: water_entity,
+                point: ray_pos,
+                time: playback.elapsed_time,
+            });
+
+            ray_dir = refract_through_water(ray_dir, normal, entering);
+            extra_segments_from_water += 1;
+            i += 1;
+            continue;
+        }
+
+        let Some((entity, intersection)) = hit else {
             let final_point = ray_pos + ray_dir * remaining_time;
             playback.elapsed_time += remaining_time;
             playback.end_point = Some(final_point);
@@ -190,7 +220,7 @@ pub fn play_light_beam(
 }
 
 #[derive(Default, Component)]
-pub struct LightBeamPoints(Vec<Vec2>);
+pub struct LightBeamPoints(pub Vec<Vec2>);
 
 /// [`System`] that runs on [`Update`], calculating the [`Transform`] of light segments from the
 /// corresponding [`LightBeamSource`]. Note that this calculation happens every frame, so instead of
@@ -210,6 +240,7 @@ pub fn simulate_light_sources(
     q_segments: Query<&LightSegment, Without<LightSegmentZMarker>>,
     light_bounce_sfx: Local<LightBounceSfx>,
     q_mirrors: Query<&Mirror>,
+    q_water: Query<(Entity, &Water, &GlobalTransform)>,
     mut ev_spark_explosion: EventWriter<SparkExplosionEvent>,
 ) {
     let Ok(rapier_context) = q_rapier.get_single_mut() else {
@@ -219,7 +250,7 @@ pub fn simulate_light_sources(
     let rapier_context = rapier_context.into_inner();
 
     for (source_entity, mut source, mut prev_playback) in q_light_sources.iter_mut() {
-        let playback = play_light_beam(rapier_context, &source, &q_black_ray, &q_mirrors);
+        let playback = play_light_beam(rapier_context, &source, &q_black_ray, &q_mirrors, &q_water);
         let mut pts: Vec<Vec2> = playback.iter_points(&source).collect();
 
         let intersections = playback.intersections.len();
@@ -238,7 +269,9 @@ pub fn simulate_light_sources(
 
                 let add_intersection = prev_x.is_none() || is_closer;
                 let remove_intersection = prev_x.is_some();
-                let play_sound = prev_x.is_none() && !is_same_intersection;
+                // beams pass through water silently
+                let play_sound =
+                    prev_x.is_none() && !is_same_intersection && !q_water.contains(new_x.entity);
 
                 // handle remove before add because it could be the case that both are true
                 if remove_intersection {
@@ -391,19 +424,30 @@ pub fn spawn_needed_segments(
 pub fn visually_sync_segments(
     q_light_sources: Query<(Entity, &LightBeamSource, &LightBeamPoints)>,
     segment_cache: Res<LightSegmentCache>,
-    mut q_segments: Query<(&Children, &mut Transform, &mut Visibility), With<LightSegment>>,
+    mut q_segments: Query<
+        (
+            &Children,
+            &mut Transform,
+            &mut Visibility,
+            &mut MeshMaterial2d<LightMaterial>,
+        ),
+        With<LightSegment>,
+    >,
     mut q_line_lights: Query<&mut LineLight2d>,
     q_light_segment_z: Query<&GlobalTransform, With<LightSegmentZMarker>>,
+    q_water: Query<(&Water, &GlobalTransform)>,
+    light_render_data: Res<LightRenderData>,
 ) {
     let Ok(light_segment_z) = q_light_segment_z.get_single() else {
         return;
     };
-    for (entity, _source, pts) in q_light_sources.iter() {
+    for (entity, source, pts) in q_light_sources.iter() {
         let pts = &pts.0;
         // use the light beam path to set the transform of the segments currently in the cache
 
         for (i, segment) in segment_cache.segments[&entity].0.iter().enumerate() {
-            let Ok((children, mut c_transform, mut c_visibility)) = q_segments.get_mut(*segment)
+            let Ok((children, mut c_transform, mut c_visibility, mut c_material)) =
+                q_segments.get_mut(*segment)
             else {
                 panic!("Segment doesn't have transform or visibility!");
             };
@@ -425,6 +469,18 @@ pub fn visually_sync_segments(
                     .with_scale(scale)
                     .with_rotation(Quat::from_rotation_z(rotation));
 
+                let underwater = q_water.iter().any(|(water, water_transform)| {
+                    water.rect(water_transform).contains(midpoint.truncate())
+                });
+                let material = if underwater {
+                    &light_render_data.water_material_map[source.color]
+                } else {
+                    &light_render_data.material_map[source.color]
+                };
+                if c_material.0 != material.0 {
+                    *c_material = material.clone();
+                }
+
                 line_light.half_length = scale.x / 2.0;
                 *c_transform = transform;
                 *c_visibility = Visibility::Visible;
@@ -445,6 +501,17 @@ pub fn tick_light_sources(mut q_light_sources: Query<&mut LightBeamSource>) {
     }
 }
 
+/// [`System`] that despawns every cached segment when the LDtk project is reloaded, since the light
+/// sources they belong to are respawned as new entities.
+pub fn clear_light_segments(mut commands: Commands, mut segment_cache: ResMut<LightSegmentCache>) {
+    for (segments, _) in segment_cache.segments.values() {
+        for &segment in segments {
+            commands.entity(segment).despawn_recursive();
+        }
+    }
+    segment_cache.segments.clear();
+}
+
 /// [`System`] that is responsible for hiding all of the [`LightSegment`](LightSegmentBundle)s
 /// and despawning [`LightBeamSource`]s when the level changes.
 pub fn cleanup_light_sources(
@@ -1,57 +1,81 @@
 use animation::SpriteAnimationPlugin;
 use bevy::prelude::*;
-use bevy::window::PresentMode;
+use bevy::window::{PresentMode, WindowMode, WindowResolution};
 use bevy::{asset::AssetMetaCheck, diagnostic::LogDiagnosticsPlugin};
 use bevy_rapier2d::prelude::*;
 
 use camera::{CameraPlugin, HIGHRES_LAYER};
+use cli::{log_cli_warnings, CliArgs};
 use config::ConfigPlugin;
 use debug::DebugPlugin;
-use input::{init_cursor_world_coords, update_cursor_world_coords};
+use input::{
+    action::InputActionPlugin,
+    init_cursor_world_coords,
+    replay::{live_input, ReplayPlugin},
+    update_aim_direction, update_cursor_world_coords, AimDirection,
+};
 use level::LevelManagementPlugin;
+use level_pack::{LevelPackPlugin, ModsAssetSourcePlugin};
 use level_select::LevelSelectPlugin;
 use light::LightManagementPlugin;
 use lighting::DeferredLightingPlugin;
 use particle::ParticlePlugin;
 use pause::PausePlugin;
 use player::PlayerManagementPlugin;
+use save::SavePlugin;
 use settings::SettingsPlugin;
 use shared::{AnimationState, GameState, ResetLevel, UiState};
 use sound::SoundPlugin;
+use speedrun::SpeedrunPlugin;
 use start_menu::StartMenuPlugin;
+use title_card::TitleCardPlugin;
 
 mod animation;
 mod camera;
+mod cli;
 mod config;
 mod debug;
 mod input;
 mod level;
+mod level_pack;
+mod level_preview;
 mod level_select;
 mod light;
 mod lighting;
 mod particle;
 mod pause;
 mod player;
+mod save;
 mod settings;
 mod shared;
 mod sound;
+mod speedrun;
 mod start_menu;
+mod title_card;
 
 fn main() {
+    let cli_args = CliArgs::from_env();
+    let mut window = Window {
+        title: "Lightborne".into(),
+        name: Some("lightborne".into()),
+        present_mode: PresentMode::AutoNoVsync,
+        canvas: Some("#bevy-container".into()),
+        fit_canvas_to_parent: true,
+        prevent_default_event_handling: false,
+        ..default()
+    };
+    if let Some(size) = cli_args.windowed {
+        window.mode = WindowMode::Windowed;
+        window.resolution = WindowResolution::new(size.x, size.y);
+    }
+
     App::new()
+        .add_plugins(ModsAssetSourcePlugin)
         .add_plugins(
             DefaultPlugins
                 .set(ImagePlugin::default_nearest())
                 .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        title: "Lightborne".into(),
-                        name: Some("lightborne".into()),
-                        present_mode: PresentMode::AutoNoVsync,
-                        canvas: Some("#bevy-container".into()),
-                        fit_canvas_to_parent: true,
-                        prevent_default_event_handling: false,
-                        ..default()
-                    }),
+                    primary_window: Some(window),
                     ..default()
                 })
                 .set(AssetPlugin {
@@ -70,7 +94,11 @@ fn main() {
             },
         )
         .add_plugins(bevy_mod_debugdump::CommandLineArgs)
+        .insert_resource(cli_args)
         .add_plugins(ConfigPlugin)
+        .add_plugins(LevelPackPlugin)
+        .add_plugins(InputActionPlugin)
+        .add_plugins(ReplayPlugin)
         .add_plugins(LogDiagnosticsPlugin::default())
         .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(8.0).in_fixed_schedule())
         .add_plugins(SpriteAnimationPlugin)
@@ -82,7 +110,10 @@ fn main() {
         .add_plugins(PausePlugin)
         .add_plugins(StartMenuPlugin)
         .add_plugins(LevelSelectPlugin)
+        .add_plugins(SavePlugin)
         .add_plugins(SettingsPlugin)
+        .add_plugins(SpeedrunPlugin)
+        .add_plugins(TitleCardPlugin)
         .add_plugins(CameraPlugin)
         .add_plugins(DebugPlugin::default())
         .insert_state(GameState::Ui)
@@ -91,7 +122,14 @@ fn main() {
         .insert_state(UiState::StartMenu)
         .add_plugins(DeferredLightingPlugin)
         .add_event::<ResetLevel>()
-        .add_systems(Startup, init_cursor_world_coords)
+        .add_systems(Startup, (log_cli_warnings, init_cursor_world_coords))
+        .init_resource::<AimDirection>()
         .add_systems(Update, update_cursor_world_coords)
+        .add_systems(
+            Update,
+            update_aim_direction
+                .after(update_cursor_world_coords)
+                .run_if(live_input),
+        )
         .run();
 }
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::player::{movement::PlayerMovement, PlayerMarker};
+
+use super::{
+    emitter::{ParticleEmitter, ParticleEmitterArea, ParticleEmitterOptions},
+    ParticleOptions, ParticlePhysicsOptions,
+};
+
+/// Marker [`Component`] for the [`ParticleEmitter`] that leaves a trail behind the player while
+/// dashing.
+#[derive(Component)]
+pub struct DashTrail;
+
+pub fn add_dash_trail(
+    mut commands: Commands,
+    q_player: Query<Entity, Added<PlayerMarker>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Ok(player) = q_player.get_single() else {
+        return;
+    };
+    commands.entity(player).with_child((
+        DashTrail,
+        ParticleEmitter::new(ParticleEmitterOptions {
+            area: ParticleEmitterArea::Cuboid {
+                half_x: 3.0,
+                half_y: 6.0,
+            },
+            delay_range: Duration::from_secs_f32(0.0)..Duration::from_secs_f32(0.02),
+            particles: vec![ParticleOptions {
+                life_time: Duration::from_secs_f32(0.3),
+                physics: Some(ParticlePhysicsOptions {
+                    wind_mult: 0.0,
+                    gravity_mult: 0.0,
+                    starting_velocity: Vec2::ZERO,
+                }),
+                sprite: Sprite {
+                    image: asset_server.load("particle/dot.png"),
+                    color: Color::srgba(1.0, 1.0, 1.0, 0.6),
+                    ..default()
+                },
+                fade_away: true,
+                ..default()
+            }],
+            ..default()
+        }),
+        Transform::from_xyz(0.0, -2.0, 0.0),
+        Visibility::Hidden,
+    ));
+}
+
+/// [`System`] that only shows the [`DashTrail`] while the player is dashing, since hidden
+/// [`ParticleEmitter`]s don't emit.
+pub fn update_dash_trail(
+    q_player: Query<&PlayerMovement, With<PlayerMarker>>,
+    mut q_dash_trail: Query<&mut Visibility, With<DashTrail>>,
+) {
+    let Ok(movement) = q_player.get_single() else {
+        return;
+    };
+    let Ok(mut visibility) = q_dash_trail.get_single_mut() else {
+        return;
+    };
+    let new_visibility = if movement.is_dashing() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    visibility.set_if_neq(new_visibility);
+}
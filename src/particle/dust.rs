@@ -7,7 +7,10 @@ use rand::{self, seq::IndexedRandom};
 
 use crate::{
     level::crystal::{CrystalColor, CrystalGroup},
-    player::{movement::PlayerMovement, PlayerMarker},
+    player::{
+        movement::{PlayerMovement, WallSide},
+        PlayerMarker,
+    },
 };
 
 use super::{ParticleBundle, ParticleOptions, ParticlePhysicsOptions};
@@ -89,6 +92,12 @@ impl DustSurface {
         player_pos + Vec2::new(0.0, -10.0) + Vec2::new(rand::random_range(-4.0..4.0), 0.0)
     }
 
+    fn new_wall_spawn_pos_from_player_pos(&self, player_pos: Vec2, wall_side: WallSide) -> Vec2 {
+        player_pos
+            + Vec2::new(wall_side.direction() * 6.0, -4.0)
+            + Vec2::new(0.0, rand::random_range(-3.0..3.0))
+    }
+
     fn new_starting_velocity(&self) -> Vec2 {
         match self {
             Self::Wall => Vec2::new(
@@ -111,6 +120,7 @@ impl DustSurface {
 pub struct DustSpawnStopwatch {
     pub walking: Stopwatch,
     pub landing: Stopwatch,
+    pub wall_sliding: Stopwatch,
 }
 
 pub fn add_crystal_dust(
@@ -141,10 +151,46 @@ pub fn spawn_player_walking_dust(
 ) {
     dust_spawn_stopwatch.walking.tick(time.delta());
     dust_spawn_stopwatch.landing.tick(time.delta());
+    dust_spawn_stopwatch.wall_sliding.tick(time.delta());
     let Ok((player_t, output, movement)) = player.get_single() else {
         return;
     };
 
+    if let Some(wall_side) = movement.wall_slide {
+        // only kick up dust once the player is actually sliding, not when they grab the wall
+        if movement.velocity.y > -0.5 {
+            return;
+        }
+        let Some(dust_surface) = output.collisions.iter().find_map(|collision| {
+            let is_wall = collision
+                .hit
+                .details
+                .is_some_and(|detail| detail.normal2.x * wall_side.direction() < 0.0);
+            if !is_wall {
+                return None;
+            }
+            dust_surfaces.get(collision.entity).ok()
+        }) else {
+            return;
+        };
+        if dust_spawn_stopwatch.wall_sliding.elapsed() <= dust_surface.spawn_interval() * 2 {
+            return;
+        }
+        dust_spawn_stopwatch.wall_sliding.reset();
+
+        let pos = dust_surface
+            .new_wall_spawn_pos_from_player_pos(player_t.translation.truncate(), wall_side);
+        let starting_velocity = Vec2::new(
+            -wall_side.direction() * rand::random_range(5.0..15.0),
+            rand::random_range(10.0..25.0),
+        );
+        commands.spawn(ParticleBundle::new(
+            dust_surface.new_particle_options(starting_velocity, &asset_server),
+            pos,
+        ));
+        return;
+    }
+
     if !output.grounded {
         return;
     }
@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
+use dash::{add_dash_trail, update_dash_trail};
 use dust::{add_crystal_dust, spawn_player_walking_dust, DustSpawnStopwatch};
 use emitter::{
     update_particle_emitters, ParticleEmitter, ParticleEmitterArea, ParticleEmitterOptions,
@@ -10,12 +11,18 @@ use shine::{add_crystal_shine, adjust_crystal_shine_lights};
 use spark::{
     add_segment_sparks, create_spark_explosions, SegmentTransformMap, SparkExplosionEvent,
 };
+use splash::{create_splashes, SplashEvent};
 
+pub mod dash;
 pub mod dust;
 pub mod emitter;
 pub mod shine;
 pub mod spark;
-use crate::level::LevelSystems;
+pub mod splash;
+use crate::level::{
+    wind::{wind_zone_force_at, WindZone},
+    LevelSystems,
+};
 pub struct ParticlePlugin;
 impl Plugin for ParticlePlugin {
     fn build(&self, app: &mut App) {
@@ -23,6 +30,7 @@ impl Plugin for ParticlePlugin {
             .insert_resource(DustSpawnStopwatch::default())
             .insert_resource(SegmentTransformMap::default())
             .add_event::<SparkExplosionEvent>()
+            .add_event::<SplashEvent>()
             .add_systems(Startup, setup)
             .add_systems(
                 Update,
@@ -39,6 +47,9 @@ impl Plugin for ParticlePlugin {
                     add_crystal_dust,
                     add_segment_sparks,
                     create_spark_explosions,
+                    create_splashes,
+                    add_dash_trail,
+                    update_dash_trail,
                 )
                     .in_set(LevelSystems::Simulation),
             );
@@ -47,19 +58,34 @@ impl Plugin for ParticlePlugin {
 
 #[derive(Resource)]
 pub struct Wind {
+    seed: u32,
     noise_1: Simplex,
     noise_2: Simplex,
+    /// Added to the time passed to [`Wind::force_at`], so that a replay sees the same gusts as the
+    /// recording it was made from.
+    pub time_offset: f32,
 }
 
 impl Wind {
     fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u32) -> Self {
         Self {
-            noise_1: Simplex::new(0),
-            noise_2: Simplex::new(1),
+            seed,
+            noise_1: Simplex::new(seed),
+            noise_2: Simplex::new(seed.wrapping_add(1)),
+            time_offset: 0.0,
         }
     }
 
-    fn force_at(&self, time: f32, pos: Vec2) -> Vec2 {
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn force_at(&self, time: f32, pos: Vec2) -> Vec2 {
+        let time = time + self.time_offset;
         let point = [time * 0.5, pos.x * 0.005, pos.y * 0.005];
         let point = [point[0] as f64, point[1] as f64, point[2] as f64];
         Vec2::new(
@@ -71,6 +97,10 @@ impl Wind {
 
 fn setup() {}
 
+/// Scales the force of [`WindZone`]s, which is in pixels per [`FixedUpdate`], into an
+/// acceleration for particles.
+const WIND_ZONE_PARTICLE_MULT: f32 = 750.0;
+
 #[derive(Default, Clone, Debug)]
 pub struct ParticleAnimationOptions {
     pub frame_time: Duration,
@@ -168,6 +198,7 @@ fn update_particles(
     mut particles: Query<(&mut Transform, &mut Particle, &mut Sprite)>,
     time: Res<Time>,
     wind: Res<Wind>,
+    q_wind_zones: Query<(&WindZone, &GlobalTransform)>,
 ) {
     for (mut transform, mut particle, mut sprite) in particles.iter_mut() {
         particle.life_timer.tick(time.delta());
@@ -182,6 +213,9 @@ fn update_particles(
             let wind_vec = wind.force_at(time.elapsed_secs(), pos);
             accel += wind_vec * time.delta_secs() * 300.0 * physics.wind_mult;
 
+            let zone_vec = wind_zone_force_at(&q_wind_zones, &wind, time.elapsed_secs(), pos);
+            accel += zone_vec * time.delta_secs() * WIND_ZONE_PARTICLE_MULT * physics.wind_mult;
+
             velocity += accel;
             particle.velocity = velocity;
             particle.pos += velocity * time.delta_secs();
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{ParticleBundle, ParticleOptions, ParticlePhysicsOptions};
+
+/// [`Event`] sent when something breaks the surface of [`Water`](crate::level::water::Water).
+#[derive(Event)]
+pub struct SplashEvent {
+    pub pos: Vec2,
+}
+
+pub fn create_splashes(
+    mut commands: Commands,
+    mut splash_events: EventReader<SplashEvent>,
+    asset_server: Res<AssetServer>,
+) {
+    const H_VEL: f32 = 30.0;
+    for event in splash_events.read() {
+        for _ in 0..12 {
+            let particle_options = ParticleOptions {
+                life_time: Duration::from_secs_f32(rand::random_range(0.4..0.7)),
+                physics: Some(ParticlePhysicsOptions {
+                    wind_mult: 0.0,
+                    gravity_mult: 150.0,
+                    starting_velocity: Vec2::new(
+                        rand::random_range(-H_VEL..H_VEL),
+                        rand::random_range(40.0..80.0),
+                    ),
+                }),
+                sprite: Sprite {
+                    image: asset_server.load("particle/dot.png"),
+                    color: Color::srgba(0.6, 0.85, 1.0, 0.8),
+                    ..default()
+                },
+                fade_away: true,
+                ..default()
+            };
+            commands.spawn(ParticleBundle::new(particle_options, event.pos));
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::widget::NodeImageMode};
 
-use crate::shared::GameState;
+use crate::{input::action::InputAction, shared::GameState};
 
 pub struct PausePlugin;
 
@@ -11,7 +11,7 @@ impl Plugin for PausePlugin {
             .add_systems(OnExit(GameState::Paused), show_pause::<false>)
             .add_systems(
                 Update,
-                toggle_pause.run_if(input_just_pressed(KeyCode::Escape)),
+                toggle_pause.run_if(input_just_pressed(InputAction::Pause)),
             );
     }
 }
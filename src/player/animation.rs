@@ -2,7 +2,12 @@ use bevy::{math::vec2, prelude::*};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    animation::AnimationConfig, input::CursorWorldCoords, level::platform::cast_player_ray_shape,
+    animation::AnimationConfig,
+    input::{
+        action::{ActionState, InputAction},
+        AimDirection,
+    },
+    level::platform::cast_player_ray_shape,
     shared::GroupLabel,
 };
 
@@ -16,9 +21,11 @@ pub enum PlayerAnimationType {
     Idle,
     Walk,
     Crouch,
+    CrouchWalk,
     Jump,
     Fall,
     Land,
+    WallSlide,
 }
 
 // HAIR, LEFT, RIGHT
@@ -75,9 +82,13 @@ impl From<PlayerAnimationType> for AnimationConfig {
             PlayerAnimationType::Walk => AnimationConfig::new(3, 10, 12, true),
             PlayerAnimationType::Idle => AnimationConfig::new(0, 2, 6, true),
             PlayerAnimationType::Crouch => AnimationConfig::new(11, 14, 48, false),
+            // shuffles between the lowest crouch frames
+            PlayerAnimationType::CrouchWalk => AnimationConfig::new(13, 14, 6, true),
             PlayerAnimationType::Jump => AnimationConfig::new(15, 20, 24, false),
             PlayerAnimationType::Fall => AnimationConfig::new(21, 24, 24, false),
             PlayerAnimationType::Land => AnimationConfig::new(25, 28, 18, false),
+            // reuses the reaching frames of the jump until Lyra gets dedicated wall frames
+            PlayerAnimationType::WallSlide => AnimationConfig::new(17, 18, 6, true),
         }
     }
 }
@@ -87,26 +98,21 @@ pub fn flip_player_direction(
         (
             &mut Sprite,
             &KinematicCharacterControllerOutput,
-            &GlobalTransform,
             &PlayerLightInventory,
         ),
         With<PlayerMarker>,
     >,
-    buttons: Res<ButtonInput<MouseButton>>,
-    q_cursor: Query<&CursorWorldCoords>,
+    actions: Res<ActionState>,
+    aim: Res<AimDirection>,
 ) {
-    let Ok((mut player_sprite, player_controller_output, player_transform, player_light_inventory)) =
+    let Ok((mut player_sprite, player_controller_output, player_light_inventory)) =
         q_player.get_single_mut()
     else {
         return;
     };
-    let Ok(cursor_coords) = q_cursor.get_single() else {
-        return;
-    };
 
-    if buttons.pressed(MouseButton::Left) && player_light_inventory.can_shoot() {
-        let to_cursor = cursor_coords.pos - player_transform.translation().xy();
-        player_sprite.flip_x = to_cursor.x < 0.0;
+    if actions.pressed(InputAction::Shoot) && player_light_inventory.can_shoot() {
+        player_sprite.flip_x = aim.0.x < 0.0;
         return;
     }
 
@@ -148,12 +154,16 @@ pub fn set_animation(
         GroupLabel::PLATFORM,
     );
 
-    let new_anim = if !output.grounded && output.effective_translation.y > 0.0 {
+    let new_anim = if movement.wall_slide.is_some() {
+        PlayerAnimationType::WallSlide
+    } else if !output.grounded && output.effective_translation.y > 0.0 {
         PlayerAnimationType::Jump
     } else if !output.grounded && entity_below_player.is_none() {
         PlayerAnimationType::Fall
     } else if output.grounded && !*was_grounded {
         PlayerAnimationType::Land
+    } else if output.grounded && movement.crouching && output.effective_translation.x.abs() > 0.05 {
+        PlayerAnimationType::CrouchWalk
     } else if output.grounded && output.effective_translation.x.abs() > 0.05 {
         PlayerAnimationType::Walk
     } else if output.grounded && movement.crouching {
@@ -0,0 +1,477 @@
+use std::{collections::BTreeMap, fmt, io, sync::Arc};
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_ecs_ldtk::LevelIid;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::HIGHRES_LAYER,
+    config::Config,
+    level::{level_completion::LevelCompletionEvent, CurrentLevel, LevelSystems},
+    level_pack::LevelPacks,
+    light::{segments::LightBeamPoints, LightBeamSource, LightColor},
+    save::{storage, ActiveSaveSlot},
+};
+
+use super::{
+    animation::set_animation, light::PlayerBeamMarker, match_player::MatchPlayerZ, PlayerMarker,
+};
+
+/// How opaque the ghost sprite is.
+const GHOST_ALPHA: f32 = 0.35;
+/// How opaque the light beams shot by the ghost are.
+const GHOST_BEAM_ALPHA: f32 = 0.15;
+
+fn slot_file_name(slot: usize) -> String {
+    format!("ghosts_{}.ron", slot + 1)
+}
+
+/// [`Plugin`] that records every run through a level, from its `StartMarker` to its `EndMarker`,
+/// and plays back the fastest one as a translucent ghost of Lyra. The fastest runs are saved into
+/// a file next to the [`ActiveSaveSlot`], see [`BestRuns::load`].
+pub struct PlayerGhostPlugin;
+
+impl Plugin for PlayerGhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BestRuns>()
+            .init_resource::<GhostRecorder>()
+            .add_systems(
+                FixedUpdate,
+                (record_ghost_sample, advance_ghost)
+                    .after(set_animation)
+                    .in_set(LevelSystems::Simulation),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_run_events,
+                    abandon_run_on_level_switch.run_if(resource_changed::<CurrentLevel>),
+                    draw_ghost_beams,
+                ),
+            );
+    }
+}
+
+/// The state of the player during a single [`FixedUpdate`] of a [`GhostRun`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct GhostSample {
+    translation: Vec2,
+    atlas_index: usize,
+    flip_x: bool,
+}
+
+/// A light beam shot during a [`GhostRun`], visible between its start and end ticks.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct GhostBeam {
+    color: LightColor,
+    start_tick: usize,
+    end_tick: usize,
+    points: Vec<Vec2>,
+}
+
+/// A recorded run through a level, with one [`GhostSample`] per [`FixedUpdate`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GhostRun {
+    samples: Vec<GhostSample>,
+    beams: Vec<GhostBeam>,
+}
+
+impl GhostRun {
+    /// The number of [`FixedUpdate`] steps the run took.
+    pub fn ticks(&self) -> usize {
+        self.samples.len()
+    }
+}
+
+/// [`Resource`] holding the fastest completed [`GhostRun`] of every level, by the path of its
+/// [`LevelPack`](crate::level_pack::LevelPack) and then by [`LevelIid`], like the personal bests
+/// in the [`SaveData`](crate::save::SaveData). Runs are shared with the [`Ghost`] playing them back.
+#[derive(Resource, Default)]
+pub struct BestRuns(pub HashMap<String, HashMap<LevelIid, Arc<GhostRun>>>);
+
+impl BestRuns {
+    fn get(&self, pack_path: &str, level_iid: &LevelIid) -> Option<&Arc<GhostRun>> {
+        self.0.get(pack_path)?.get(level_iid)
+    }
+
+    /// Keeps the run if it is the fastest one of the level so far. Returns whether it was kept.
+    fn submit(&mut self, pack_path: &str, level_iid: &LevelIid, run: GhostRun) -> bool {
+        let is_best = self
+            .get(pack_path, level_iid)
+            .is_none_or(|best_run| run.ticks() < best_run.ticks());
+        if is_best {
+            self.0
+                .entry(pack_path.to_owned())
+                .or_default()
+                .insert(level_iid.clone(), Arc::new(run));
+        }
+        is_best
+    }
+
+    /// Loads the runs saved with a save slot, which are empty if the slot has none.
+    pub fn load(slot: usize) -> Result<Self, GhostsError> {
+        let Some(contents) = storage::read(&slot_file_name(slot))? else {
+            return Ok(BestRuns::default());
+        };
+        Self::parse(&contents)
+    }
+
+    fn parse(contents: &str) -> Result<Self, GhostsError> {
+        let stored: BTreeMap<String, BTreeMap<String, GhostRun>> = ron::from_str(contents)?;
+        Ok(BestRuns(
+            stored
+                .into_iter()
+                .map(|(pack_path, runs)| {
+                    let runs = runs
+                        .into_iter()
+                        .map(|(level_iid, run)| (LevelIid::new(level_iid), Arc::new(run)))
+                        .collect();
+                    (pack_path, runs)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Writes the runs next to a save slot, overwriting what was there.
+    pub fn write(&self, slot: usize) -> Result<(), GhostsError> {
+        storage::write(&slot_file_name(slot), &self.serialize()?)?;
+        Ok(())
+    }
+
+    fn serialize(&self) -> Result<String, GhostsError> {
+        let stored: BTreeMap<&str, BTreeMap<&str, &GhostRun>> = self
+            .0
+            .iter()
+            .map(|(pack_path, runs)| {
+                let runs = runs
+                    .iter()
+                    .map(|(level_iid, run)| (level_iid.as_str(), run.as_ref()))
+                    .collect();
+                (pack_path.as_str(), runs)
+            })
+            .collect();
+        Ok(ron::to_string(&stored)?)
+    }
+
+    pub fn delete(slot: usize) -> Result<(), GhostsError> {
+        storage::remove(&slot_file_name(slot))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum GhostsError {
+    Io(io::Error),
+    Parse(ron::de::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for GhostsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not access ghosts: {err}"),
+            Self::Parse(err) => write!(f, "could not parse ghosts: {err}"),
+            Self::Serialize(err) => write!(f, "could not serialize ghosts: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for GhostsError {}
+
+impl From<io::Error> for GhostsError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::de::SpannedError> for GhostsError {
+    fn from(err: ron::de::SpannedError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<ron::Error> for GhostsError {
+    fn from(err: ron::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+/// [`Resource`] holding the [`GhostRun`] in progress, if the player is in the middle of a level.
+#[derive(Resource, Default)]
+struct GhostRecorder {
+    level_iid: Option<LevelIid>,
+    run: GhostRun,
+    /// The index into [`GhostRun::beams`] of every [`LightBeamSource`] shot during the run.
+    beam_indices: HashMap<Entity, usize>,
+}
+
+impl GhostRecorder {
+    /// Starts recording a new run through the level, reusing the memory of the previous one.
+    fn restart(&mut self, level_iid: &LevelIid) {
+        if self.level_iid.as_ref() != Some(level_iid) {
+            self.level_iid = Some(level_iid.clone());
+        }
+        self.run.samples.clear();
+        self.run.beams.clear();
+        self.beam_indices.clear();
+    }
+}
+
+/// [`Component`] for the ghost, which follows the [`GhostRun`] it was spawned with.
+#[derive(Component)]
+struct Ghost {
+    level_iid: LevelIid,
+    run: Arc<GhostRun>,
+    tick: usize,
+}
+
+/// [`System`] that starts recording (and restarts the ghost) when the player starts a level, and
+/// keeps the recording if it is the fastest when they complete it. `Started` is sent every frame
+/// while the player stands on the `StartMarker`, so that the run starts when they step off it.
+///
+/// New best runs are saved with the [`ActiveSaveSlot`], unless every level is unlocked for
+/// debugging, like the personal bests.
+#[allow(clippy::too_many_arguments)]
+fn handle_run_events(
+    mut commands: Commands,
+    mut ev_completion: EventReader<LevelCompletionEvent>,
+    mut recorder: ResMut<GhostRecorder>,
+    mut best_runs: ResMut<BestRuns>,
+    mut q_ghost: Query<(Entity, &mut Ghost)>,
+    q_player: Query<&Sprite, With<PlayerMarker>>,
+    level_packs: Res<LevelPacks>,
+    active_slot: Res<ActiveSaveSlot>,
+    config: Res<Config>,
+) {
+    let pack_path = &level_packs.active().path;
+    for event in ev_completion.read() {
+        match event {
+            LevelCompletionEvent::Started(level_iid) => {
+                recorder.restart(level_iid);
+
+                let Some(best_run) = best_runs.get(pack_path, level_iid) else {
+                    continue;
+                };
+                if let Ok((_, mut existing)) = q_ghost.get_single_mut() {
+                    if existing.level_iid != *level_iid || !Arc::ptr_eq(&existing.run, best_run) {
+                        existing.level_iid = level_iid.clone();
+                        existing.run = Arc::clone(best_run);
+                    }
+                    existing.tick = 0;
+                    continue;
+                }
+                let ghost = Ghost {
+                    level_iid: level_iid.clone(),
+                    run: Arc::clone(best_run),
+                    tick: 0,
+                };
+                let Ok(player_sprite) = q_player.get_single() else {
+                    continue;
+                };
+                commands.spawn((
+                    ghost,
+                    Sprite {
+                        color: Color::WHITE.with_alpha(GHOST_ALPHA),
+                        ..player_sprite.clone()
+                    },
+                    Visibility::Hidden,
+                    MatchPlayerZ { offset: -0.5 },
+                    HIGHRES_LAYER,
+                ));
+            }
+            LevelCompletionEvent::Completed(level_iid) => {
+                if recorder.level_iid.as_ref() != Some(level_iid) {
+                    continue;
+                }
+                recorder.level_iid = None;
+                let run = std::mem::take(&mut recorder.run);
+                let ticks = run.ticks();
+                if !best_runs.submit(pack_path, level_iid, run) {
+                    continue;
+                }
+                info!("New best run of {ticks} ticks");
+                let Some(slot) = active_slot.0 else {
+                    continue;
+                };
+                if config.debug_config.unlock_levels {
+                    continue;
+                }
+                if let Err(err) = best_runs.write(slot) {
+                    error!("Could not save best run: {err}");
+                }
+            }
+        }
+    }
+}
+
+/// [`System`] that throws away the run in progress and the ghost when the player leaves the level.
+fn abandon_run_on_level_switch(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    mut recorder: ResMut<GhostRecorder>,
+    q_ghost: Query<(Entity, &Ghost)>,
+) {
+    if recorder
+        .level_iid
+        .as_ref()
+        .is_some_and(|level_iid| *level_iid != current_level.level_iid)
+    {
+        *recorder = GhostRecorder::default();
+    }
+    for (entity, ghost) in q_ghost.iter() {
+        if ghost.level_iid != current_level.level_iid {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// [`System`] that samples the player and the light beams they shot into the run in progress.
+fn record_ghost_sample(
+    mut recorder: ResMut<GhostRecorder>,
+    q_player: Query<(&Transform, &Sprite), With<PlayerMarker>>,
+    q_light_sources: Query<(Entity, &LightBeamSource, &LightBeamPoints), With<PlayerBeamMarker>>,
+) {
+    if recorder.level_iid.is_none() {
+        return;
+    }
+    let Ok((transform, sprite)) = q_player.get_single() else {
+        return;
+    };
+    let recorder = &mut *recorder;
+
+    let tick = recorder.run.samples.len();
+    recorder.run.samples.push(GhostSample {
+        translation: transform.translation.truncate(),
+        atlas_index: sprite.texture_atlas.as_ref().map_or(0, |atlas| atlas.index),
+        flip_x: sprite.flip_x,
+    });
+
+    for (entity, source, points) in q_light_sources.iter() {
+        match recorder.beam_indices.get(&entity) {
+            Some(&index) => {
+                let beam = &mut recorder.run.beams[index];
+                beam.end_tick = tick;
+                beam.points.clone_from(&points.0);
+            }
+            None => {
+                recorder
+                    .beam_indices
+                    .insert(entity, recorder.run.beams.len());
+                recorder.run.beams.push(GhostBeam {
+                    color: source.color,
+                    start_tick: tick,
+                    end_tick: tick,
+                    points: points.0.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// [`System`] that moves the ghost along its run, hiding it once the run is over.
+fn advance_ghost(mut q_ghost: Query<(&mut Ghost, &mut Transform, &mut Sprite, &mut Visibility)>) {
+    for (mut ghost, mut transform, mut sprite, mut visibility) in q_ghost.iter_mut() {
+        let Some(sample) = ghost.run.samples.get(ghost.tick).copied() else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        ghost.tick += 1;
+        transform.translation.x = sample.translation.x;
+        transform.translation.y = sample.translation.y;
+        if let Some(atlas) = sprite.texture_atlas.as_mut() {
+            atlas.index = sample.atlas_index;
+        }
+        sprite.flip_x = sample.flip_x;
+        *visibility = Visibility::Visible;
+    }
+}
+
+/// [`System`] that draws the light beams the ghost had shot at its current tick as faint lines.
+fn draw_ghost_beams(mut gizmos: Gizmos, q_ghost: Query<(&Ghost, &Visibility)>) {
+    for (ghost, visibility) in q_ghost.iter() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        // the tick has already been advanced past the sample the ghost is showing
+        let Some(tick) = ghost.tick.checked_sub(1) else {
+            continue;
+        };
+        for beam in ghost
+            .run
+            .beams
+            .iter()
+            .filter(|beam| (beam.start_tick..=beam.end_tick).contains(&tick))
+        {
+            gizmos.linestrip_2d(
+                beam.points.iter().copied(),
+                beam.color.indicator_color().with_alpha(GHOST_BEAM_ALPHA),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_of(ticks: usize) -> GhostRun {
+        GhostRun {
+            samples: vec![
+                GhostSample {
+                    translation: Vec2::ZERO,
+                    atlas_index: 0,
+                    flip_x: false,
+                };
+                ticks
+            ],
+            beams: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn only_faster_runs_replace_the_best_run() {
+        let level_iid = LevelIid::new("level");
+        let mut best_runs = BestRuns::default();
+        assert!(best_runs.submit("a.ldtk", &level_iid, run_of(10)));
+        assert!(best_runs.submit("a.ldtk", &level_iid, run_of(5)));
+        assert!(!best_runs.submit("a.ldtk", &level_iid, run_of(8)));
+        assert!(!best_runs.submit("a.ldtk", &level_iid, run_of(5)));
+        assert_eq!(best_runs.get("a.ldtk", &level_iid).unwrap().ticks(), 5);
+        // packs copied from each other share level iids
+        assert!(best_runs.submit("b.ldtk", &level_iid, run_of(8)));
+        assert_eq!(best_runs.get("b.ldtk", &level_iid).unwrap().ticks(), 8);
+    }
+
+    #[test]
+    fn best_runs_round_trip() {
+        let level_iid = LevelIid::new("level");
+        let mut run = run_of(3);
+        run.beams.push(GhostBeam {
+            color: LightColor::Blue,
+            start_tick: 1,
+            end_tick: 2,
+            points: vec![Vec2::ZERO, Vec2::new(10.0, 5.0)],
+        });
+        let mut best_runs = BestRuns::default();
+        best_runs.submit("a.ldtk", &level_iid, run);
+
+        let parsed = BestRuns::parse(&best_runs.serialize().unwrap()).unwrap();
+        let parsed_run = parsed.get("a.ldtk", &level_iid).unwrap();
+        assert_eq!(parsed_run.ticks(), 3);
+        assert_eq!(parsed_run.beams[0].color, LightColor::Blue);
+        assert_eq!(parsed_run.beams[0].points[1], Vec2::new(10.0, 5.0));
+    }
+
+    #[test]
+    fn restarting_the_same_level_clears_the_run() {
+        let level_iid = LevelIid::new("level");
+        let mut recorder = GhostRecorder::default();
+        recorder.restart(&level_iid);
+        recorder.run = run_of(3);
+        recorder.beam_indices.insert(Entity::PLACEHOLDER, 0);
+        recorder.restart(&level_iid);
+        assert_eq!(recorder.level_iid, Some(level_iid));
+        assert_eq!(recorder.run.ticks(), 0);
+        assert!(recorder.beam_indices.is_empty());
+    }
+}
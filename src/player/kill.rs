@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use bevy::{ecs::system::SystemId, input::common_conditions::input_just_pressed, prelude::*};
+use bevy::{ecs::system::SystemId, prelude::*};
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
 
@@ -9,9 +9,10 @@ use crate::{
         camera_position_from_level, CameraControlType, CameraMoveEvent, CameraTransition,
         CameraTransitionEvent,
     },
+    input::action::{tick_just_pressed, InputAction},
     level::{
-        entity::HurtMarker, shard::reset_shard_effects_on_kill, start_flag::StartFlag,
-        CurrentLevel, LevelSystems,
+        checkpoint::ActiveCheckpoint, entity::HurtMarker, shard::reset_shard_effects_on_kill,
+        start_flag::StartFlag, CurrentLevel, LevelSystems,
     },
     shared::{AnimationState, GameState, ResetLevel, LYRA_RESPAWN_EPSILON},
 };
@@ -31,16 +32,19 @@ impl Plugin for PlayerKillPlugin {
             .add_systems(Update, reset_player_on_kill.in_set(LevelSystems::Reset))
             .add_systems(
                 Update,
-                (
-                    quick_reset
-                        .run_if(input_just_pressed(KeyCode::KeyR))
-                        .run_if(in_state(GameState::Playing)),
-                    // reset player will try to preserve the current color, the calculations for
-                    // which depend on proper values for the current level's allowed colors
-                    reset_player_on_level_switch
-                        .after(reset_shard_effects_on_kill)
-                        .in_set(LevelSystems::Reset),
-                ),
+                // reset player will try to preserve the current color, the calculations for which
+                // depend on proper values for the current level's allowed colors
+                reset_player_on_level_switch
+                    .after(reset_shard_effects_on_kill)
+                    .in_set(LevelSystems::Reset),
+            )
+            .add_systems(
+                FixedUpdate,
+                quick_reset
+                    .run_if(tick_just_pressed(InputAction::Reset))
+                    .run_if(in_state(GameState::Playing))
+                    .before(start_kill_animation)
+                    .before(play_death_sound_on_kill),
             )
             .add_systems(
                 FixedUpdate,
@@ -54,7 +58,7 @@ impl Plugin for PlayerKillPlugin {
     }
 }
 
-/// [`System`] that will kill the player on press of the R key
+/// [`System`] that will kill the player on press of the [`InputAction::Reset`] binding
 pub fn quick_reset(mut ev_kill_player: EventWriter<KillPlayerEvent>) {
     ev_kill_player.send(KillPlayerEvent);
 }
@@ -74,7 +78,9 @@ pub fn play_death_sound_on_kill(
 }
 
 /// [`System`] that runs on [`GameState::Respawning`]. Will turn the state back into playing
-/// immediately.
+/// immediately. The player respawns at the [`ActiveCheckpoint`] if they touched one in the current
+/// level, otherwise at the level's [`StartFlag`].
+#[allow(clippy::too_many_arguments)]
 pub fn reset_player_on_kill(
     mut commands: Commands,
     // angle marker despawn should realistically happen in a diff system?
@@ -82,6 +88,7 @@ pub fn reset_player_on_kill(
     mut ev_reset_level: EventReader<ResetLevel>,
     q_start_flag: Query<(&StartFlag, &EntityInstance)>,
     current_level: Res<CurrentLevel>,
+    active_checkpoint: Res<ActiveCheckpoint>,
     mut ev_move_camera: EventWriter<CameraMoveEvent>,
     mut q_player: Query<&mut Transform, With<PlayerMarker>>,
 ) {
@@ -97,26 +104,29 @@ pub fn reset_player_on_kill(
         commands.entity(angle_marker).despawn_recursive();
     }
 
-    for (flag, instance) in q_start_flag.iter() {
-        if current_level.level_iid == flag.level_iid {
-            player_transform.translation.x =
-                instance.world_x.expect("Lightborne uses Free world layout") as f32;
-            player_transform.translation.y =
-                -instance.world_y.expect("Lightborne uses Free world layout") as f32
-                    + LYRA_RESPAWN_EPSILON;
-            // add small height so Lyra is not stuck into the floor
-            ev_move_camera.send(CameraMoveEvent {
-                to: camera_position_from_level(
-                    current_level.level_box,
-                    player_transform.translation.xy(),
-                ),
-                variant: CameraControlType::Instant,
-            });
-            return;
-        }
-    }
-
-    panic!("Couldn't find start flag to respawn at");
+    let respawn_position = active_checkpoint
+        .respawn_position(&current_level.level_iid)
+        .or_else(|| {
+            q_start_flag
+                .iter()
+                .find(|(flag, _)| current_level.level_iid == flag.level_iid)
+                .map(|(_, instance)| {
+                    // add small height so Lyra is not stuck into the floor
+                    Vec2::new(
+                        instance.world_x.expect("Lightborne uses Free world layout") as f32,
+                        -instance.world_y.expect("Lightborne uses Free world layout") as f32
+                            + LYRA_RESPAWN_EPSILON,
+                    )
+                })
+        })
+        .expect("Couldn't find start flag to respawn at");
+
+    player_transform.translation.x = respawn_position.x;
+    player_transform.translation.y = respawn_position.y;
+    ev_move_camera.send(CameraMoveEvent {
+        to: camera_position_from_level(current_level.level_box, player_transform.translation.xy()),
+        variant: CameraControlType::Instant,
+    });
 }
 
 /// Resets the player inventory and movement information on a [`LevelSwitchEvent`]
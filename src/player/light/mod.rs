@@ -1,8 +1,5 @@
 use bevy::{
-    input::{
-        common_conditions::{input_just_pressed, input_just_released, input_pressed},
-        mouse::MouseWheel,
-    },
+    input::common_conditions::{input_just_pressed, input_just_released, input_pressed},
     prelude::*,
 };
 use bevy_rapier2d::plugin::RapierContext;
@@ -15,20 +12,28 @@ use std::f32::consts::PI;
 
 use crate::{
     camera::{HIGHRES_LAYER, TERRAIN_LAYER},
-    input::{update_cursor_world_coords, CursorWorldCoords},
-    level::{mirror::Mirror, CurrentLevel, LevelSystems},
+    input::{
+        action::{tick_just_pressed, tick_just_released, tick_pressed, InputAction, TickInput},
+        replay::live_input,
+        update_aim_direction, AimDirection,
+    },
+    level::{mirror::Mirror, sensor::LightSensor, water::Water, CurrentLevel, LevelSystems},
     light::{
         segments::{play_light_beam, PrevLightBeamPlayback},
         BlackRayComponent, LightBeamSource, LightColor, LightSourceZMarker,
     },
     lighting::LineLight2d,
+    settings::Settings,
 };
 use indicator::LightIndicatorPlugin;
 
 mod indicator;
 mod ui;
 
-const NUMINCREMENTS: i32 = 16; // The number of angle increments for light beam alignment
+/// The largest angle the aim assist will nudge the light beam by.
+const AIM_ASSIST_MAX_ANGLE: f32 = PI / 24.0;
+/// The number of angles tried on each side of the aim direction by the aim assist.
+const AIM_ASSIST_STEPS: i32 = 10;
 
 use super::{not_input_locked, PlayerMarker};
 
@@ -38,42 +43,49 @@ impl Plugin for PlayerLightPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(LightIndicatorPlugin)
             .add_plugins(LightUiPlugin)
+            .init_resource::<BeamAim>()
             .add_systems(
-                Update,
+                FixedUpdate,
                 (
                     handle_color_switch,
-                    should_shoot_light::<true>.run_if(input_just_pressed(MouseButton::Left)),
-                    should_shoot_light::<false>.run_if(input_just_pressed(MouseButton::Right)),
+                    should_shoot_light::<true>.run_if(tick_just_pressed(InputAction::Shoot)),
+                    should_shoot_light::<false>.run_if(tick_just_pressed(InputAction::CancelShot)),
+                    update_beam_aim.run_if(live_input).run_if(
+                        tick_pressed(InputAction::Shoot).or(tick_just_released(InputAction::Shoot)),
+                    ),
+                    shoot_light.run_if(tick_just_released(InputAction::Shoot)),
+                )
+                    .chain()
+                    .run_if(not_input_locked)
+                    .in_set(LevelSystems::Simulation),
+            )
+            .add_systems(
+                Update,
+                (
                     preview_light_path,
                     spawn_angle_indicator.run_if(
-                        input_just_pressed(MouseButton::Left).or((input_just_released(
-                            KeyCode::ShiftLeft,
-                        )
-                        .or(input_just_released(KeyCode::ShiftRight)))
-                        .and(input_pressed(MouseButton::Left))),
+                        input_just_pressed(InputAction::Shoot)
+                            .or(input_just_released(InputAction::SnapAim)
+                                .and(input_pressed(InputAction::Shoot))),
                     ),
                     despawn_angle_indicator.run_if(
-                        input_just_released(MouseButton::Left)
-                            .or(input_just_pressed(MouseButton::Right))
-                            .or(input_just_pressed(KeyCode::ShiftLeft))
-                            .or(input_just_pressed(KeyCode::ShiftRight)),
+                        input_just_released(InputAction::Shoot)
+                            .or(input_just_pressed(InputAction::CancelShot))
+                            .or(input_just_pressed(InputAction::SnapAim)),
                     ),
                     spawn_angle_increments_indicators.run_if(
-                        input_just_pressed(KeyCode::ShiftLeft)
-                            .or(input_just_pressed(KeyCode::ShiftRight))
-                            .and(input_pressed(MouseButton::Left)),
+                        input_just_pressed(InputAction::SnapAim)
+                            .and(input_pressed(InputAction::Shoot)),
                     ),
                     despawn_angle_increments_indicators.run_if(
-                        input_just_released(KeyCode::ShiftLeft)
-                            .or(input_just_released(KeyCode::ShiftRight))
-                            .or(input_just_pressed(MouseButton::Right)),
+                        input_just_released(InputAction::SnapAim)
+                            .or(input_just_pressed(InputAction::CancelShot)),
                     ),
-                    shoot_light.run_if(input_just_released(MouseButton::Left)),
                 )
                     .chain()
                     .run_if(not_input_locked)
                     .in_set(LevelSystems::Simulation)
-                    .after(update_cursor_world_coords),
+                    .after(update_aim_direction),
             );
     }
 }
@@ -145,9 +157,13 @@ pub fn spawn_angle_increments_indicators(
     q_player: Query<Entity, With<PlayerMarker>>,
     q_angle: Query<Entity, With<AngleIncrementMarker>>,
     asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
 ) {
-    for i in 0..NUMINCREMENTS {
-        let angle_increment = (2.0 * PI) / NUMINCREMENTS as f32;
+    let Some(num_increments) = settings.snap_increments() else {
+        return;
+    };
+    for i in 0..num_increments {
+        let angle_increment = (2.0 * PI) / num_increments as f32;
         let Ok(player) = q_player.get_single() else {
             return;
         };
@@ -187,10 +203,9 @@ pub fn despawn_angle_increments_indicators(
     }
 }
 
-/// [`System`] to handle the keyboard presses corresponding to color switches.
+/// [`System`] to handle the [`InputAction`]s corresponding to color switches.
 pub fn handle_color_switch(
-    keys: Res<ButtonInput<KeyCode>>,
-    mut ev_scroll: EventReader<MouseWheel>,
+    input: Res<TickInput>,
     mut q_inventory: Query<&mut PlayerLightInventory, With<PlayerMarker>>,
     current_level: Res<CurrentLevel>,
 ) {
@@ -198,12 +213,12 @@ pub fn handle_color_switch(
         return;
     };
 
-    static COLOR_BINDS: [(KeyCode, LightColor); 5] = [
-        (KeyCode::Digit1, LightColor::Green),
-        (KeyCode::Digit2, LightColor::Purple),
-        (KeyCode::Digit3, LightColor::White),
-        (KeyCode::Digit4, LightColor::Blue),
-        (KeyCode::Digit5, LightColor::Black),
+    static COLOR_BINDS: [(InputAction, LightColor); 5] = [
+        (InputAction::ColorGreen, LightColor::Green),
+        (InputAction::ColorPurple, LightColor::Purple),
+        (InputAction::ColorWhite, LightColor::White),
+        (InputAction::ColorBlue, LightColor::Blue),
+        (InputAction::ColorBlack, LightColor::Black),
     ];
 
     let mut cur_index = match inventory.current_color {
@@ -215,8 +230,16 @@ pub fn handle_color_switch(
         Some(LightColor::Black) => 4,
     };
 
-    for scroll in ev_scroll.read() {
-        let sign = -(scroll.y.signum() as i32);
+    // the scroll wheel and the cycle actions both step through the colors
+    let actions = &input.actions;
+    let steps = input
+        .scroll
+        .iter()
+        .map(|y| -(y.signum() as i32))
+        .chain(actions.just_pressed(InputAction::ColorPrev).then_some(-1))
+        .chain(actions.just_pressed(InputAction::ColorNext).then_some(1))
+        .collect::<Vec<_>>();
+    for sign in steps {
         let mut new_index = cur_index + sign;
 
         // suspicious algorithm to cycle through available colors with the scroll wheel
@@ -234,8 +257,8 @@ pub fn handle_color_switch(
         }
     }
 
-    for (key, color) in COLOR_BINDS {
-        if keys.just_pressed(key) && current_level.allowed_colors[color] {
+    for (action, color) in COLOR_BINDS {
+        if actions.just_pressed(action) && current_level.allowed_colors[color] {
             inventory.current_color = Some(color);
         }
     }
@@ -250,12 +273,16 @@ pub fn should_shoot_light<const V: bool>(
     inventory.should_shoot = V;
 }
 
+/// [`Component`] for the [`LightBeamSource`]s shot by the player, as opposed to the beams that are
+/// part of the level.
+#[derive(Component, Default, Debug)]
+pub struct PlayerBeamMarker;
+
 pub fn shoot_light(
     mut commands: Commands,
     mut q_player: Query<(&Transform, &mut PlayerLightInventory), With<PlayerMarker>>,
     q_light_source_z: Query<&Transform, With<LightSourceZMarker>>,
-    q_cursor: Query<&CursorWorldCoords>,
-    keys: Res<ButtonInput<KeyCode>>,
+    beam_aim: Res<BeamAim>,
     asset_server: Res<AssetServer>,
 ) {
     let Ok((player_transform, mut player_inventory)) = q_player.get_single_mut() else {
@@ -264,19 +291,12 @@ pub fn shoot_light(
     let Ok(light_source_z) = q_light_source_z.get_single() else {
         return;
     };
-    let Ok(cursor_pos) = q_cursor.get_single() else {
-        return;
-    };
     if !player_inventory.can_shoot() {
         return;
     }
 
     let ray_pos = player_transform.translation.truncate();
-    let mut ray_dir = (cursor_pos.pos - ray_pos).normalize_or_zero();
-
-    if keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight) {
-        ray_dir = snap_ray(ray_dir);
-    }
+    let ray_dir = beam_aim.0;
 
     if ray_dir == Vec2::ZERO {
         return;
@@ -300,6 +320,7 @@ pub fn shoot_light(
             color: shoot_color,
         })
         .insert(PrevLightBeamPlayback::default())
+        .insert(PlayerBeamMarker)
         .insert(HIGHRES_LAYER)
         .insert(source_sprite)
         .insert(source_transform)
@@ -319,16 +340,72 @@ pub fn shoot_light(
 #[derive(Default, Reflect, GizmoConfigGroup)]
 pub struct LightPreviewGizmos;
 
-/// [`System`] that uses [`Gizmos`] to preview the light path while the left mouse button is held
-/// down. This system needs some work, namely:
+/// [`Resource`] holding the direction the light beam will be shot in, after snapping and aim
+/// assist. Set by [`update_beam_aim`] so that the preview always matches the shot, or by the replay.
+#[derive(Resource, Default)]
+pub struct BeamAim(pub Vec2);
+
+/// [`System`] that updates the [`BeamAim`] every tick the player is aiming and ready to shoot. If aim assist
+/// is on, the aim is nudged toward nearby angles where the beam would hit a [`LightSensor`].
+#[allow(clippy::too_many_arguments)]
+pub fn update_beam_aim(
+    mut q_rapier: Query<&mut RapierContext>,
+    q_player: Query<(&Transform, &PlayerLightInventory), With<PlayerMarker>>,
+    aim: Res<AimDirection>,
+    input: Res<TickInput>,
+    settings: Res<Settings>,
+    mut beam_aim: ResMut<BeamAim>,
+    q_mirror: Query<&Mirror>,
+    q_water: Query<(Entity, &Water, &GlobalTransform)>,
+    q_sensor: Query<(), With<LightSensor>>,
+    q_black_ray: Query<(Entity, &BlackRayComponent)>,
+) {
+    let Ok(rapier_context) = q_rapier.get_single_mut() else {
+        return;
+    };
+    let Ok((transform, inventory)) = q_player.get_single() else {
+        return;
+    };
+    if !inventory.can_shoot() {
+        return;
+    }
+
+    let snap = settings
+        .snap_increments()
+        .filter(|_| input.actions.pressed(InputAction::SnapAim));
+
+    beam_aim.0 = if let Some(num_increments) = snap {
+        snap_ray(aim.0, num_increments)
+    } else if settings.aim_assist() && aim.0 != Vec2::ZERO {
+        let source = LightBeamSource {
+            start_pos: transform.translation.truncate(),
+            start_dir: aim.0,
+            time_traveled: 10000.0,
+            color: inventory.current_color.unwrap(),
+        };
+        assist_aim(
+            rapier_context.into_inner(),
+            &source,
+            &q_black_ray,
+            &q_mirror,
+            &q_water,
+            &q_sensor,
+        )
+    } else {
+        aim.0
+    };
+}
+
+/// [`System`] that uses [`Gizmos`] to preview the light path along the [`BeamAim`] while the left
+/// mouse button is held down. This system needs some work, namely:
 ///
 /// - Not using [`Gizmos`] to render the light segments
 pub fn preview_light_path(
     mut q_rapier: Query<&mut RapierContext>,
     q_player: Query<(&Transform, &PlayerLightInventory), With<PlayerMarker>>,
-    q_cursor: Query<&CursorWorldCoords>,
-    keys: Res<ButtonInput<KeyCode>>,
+    beam_aim: Res<BeamAim>,
     q_mirror: Query<&Mirror>,
+    q_water: Query<(Entity, &Water, &GlobalTransform)>,
     mut gizmos: Gizmos,
     q_black_ray: Query<(Entity, &BlackRayComponent)>,
 ) {
@@ -338,33 +415,26 @@ pub fn preview_light_path(
     let Ok((transform, inventory)) = q_player.get_single() else {
         return;
     };
-    let Ok(cursor_pos) = q_cursor.get_single() else {
-        return;
-    };
     if !inventory.can_shoot() {
         return;
     }
 
     let shoot_color = inventory.current_color.unwrap();
-
-    let ray_pos = transform.translation.truncate();
-    let mut ray_dir = (cursor_pos.pos - ray_pos).normalize_or_zero();
-
-    if keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight) {
-        ray_dir = snap_ray(ray_dir);
-    }
+    let rapier_context = rapier_context.into_inner();
 
     let dummy_source = LightBeamSource {
-        start_pos: ray_pos,
-        start_dir: ray_dir,
+        start_pos: transform.translation.truncate(),
+        start_dir: beam_aim.0,
         time_traveled: 10000.0, // LOL
         color: shoot_color,
     };
+
     let playback = play_light_beam(
-        rapier_context.into_inner(),
+        rapier_context,
         &dummy_source,
         &q_black_ray,
         &q_mirror,
+        &q_water,
     );
 
     for (a, b) in playback.iter_points(&dummy_source).tuple_windows() {
@@ -372,9 +442,41 @@ pub fn preview_light_path(
     }
 }
 
-fn snap_ray(ray_vec: Vec2) -> Vec2 {
+/// Finds the angle closest to the `source`'s direction, within [`AIM_ASSIST_MAX_ANGLE`], where the
+/// light beam would hit a [`LightSensor`]. Returns the original direction if there is none.
+fn assist_aim(
+    rapier_context: &mut RapierContext,
+    source: &LightBeamSource,
+    q_black_ray: &Query<(Entity, &BlackRayComponent)>,
+    q_mirror: &Query<&Mirror>,
+    q_water: &Query<(Entity, &Water, &GlobalTransform)>,
+    q_sensor: &Query<(), With<LightSensor>>,
+) -> Vec2 {
+    // try the angles closest to the aim first, so the beam is nudged as little as possible
+    let offsets = (0..=AIM_ASSIST_STEPS).flat_map(|step| {
+        let offset = step as f32 * AIM_ASSIST_MAX_ANGLE / AIM_ASSIST_STEPS as f32;
+        [offset, -offset]
+    });
+    for offset in offsets {
+        let candidate = LightBeamSource {
+            start_dir: Vec2::from_angle(offset).rotate(source.start_dir),
+            ..*source
+        };
+        let playback = play_light_beam(rapier_context, &candidate, q_black_ray, q_mirror, q_water);
+        let hits_sensor = playback
+            .intersections
+            .iter()
+            .any(|intersection| q_sensor.contains(intersection.entity));
+        if hits_sensor {
+            return candidate.start_dir;
+        }
+    }
+    source.start_dir
+}
+
+fn snap_ray(ray_vec: Vec2, num_increments: i32) -> Vec2 {
     let ray_angle = (ray_vec.y.atan2(ray_vec.x) + (2.0 * PI)) % (2.0 * PI);
-    let increment_angle = (2.0 * PI) / NUMINCREMENTS as f32;
+    let increment_angle = (2.0 * PI) / num_increments as f32;
     let snapped_angle = (ray_angle / increment_angle).round() * increment_angle;
 
     Vec2::new(cos(snapped_angle), sin(snapped_angle))
@@ -2,6 +2,7 @@ use animation::{flip_player_direction, set_animation, PlayerAnimationType};
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
+use ghost::PlayerGhostPlugin;
 use match_player::{
     post_update_match_player_pixel, pre_update_match_player_pixel, update_match_player_z,
 };
@@ -13,14 +14,17 @@ use kill::PlayerKillPlugin;
 use light::{PlayerLightInventory, PlayerLightPlugin};
 use movement::{PlayerMovement, PlayerMovementPlugin};
 use spawn::{add_player_sensors, init_player_bundle};
+use tuning::MovementTuningPlugin;
 
 mod animation;
+pub mod ghost;
 pub mod kill;
 pub mod light;
 pub mod match_player;
 pub mod movement;
 mod spawn;
 mod strand;
+pub mod tuning;
 
 /// [`Plugin`] for anything player based.
 pub struct PlayerManagementPlugin;
@@ -29,8 +33,10 @@ impl Plugin for PlayerManagementPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(PlayerLightPlugin)
             .add_plugins(PlayerMovementPlugin)
+            .add_plugins(MovementTuningPlugin)
             .add_plugins(PlayerKillPlugin)
             .add_plugins(PlayerStrandPlugin)
+            .add_plugins(PlayerGhostPlugin)
             .add_systems(
                 PreUpdate,
                 add_player_sensors.in_set(LevelSystems::Processing),
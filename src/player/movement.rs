@@ -1,28 +1,19 @@
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::level::LevelSystems;
-use crate::config::Config;
-
-use super::{not_input_locked, InputLocked, PlayerMarker};
-
-/// The number of [`FixedUpdate`] steps the player can jump for after pressing the spacebar.
-const SHOULD_JUMP_TICKS: isize = 8;
-/// The number of [`FixedUpdate`] steps the player can jump for after falling off an edge.
-const COYOTE_TIME_TICKS: isize = 5;
-/// The number of [`FixedUpdate`] steps the player should receive upward velocity for.
-const JUMP_BOOST_TICKS: isize = 2;
-
-/// Max player horizontal velocity.
-const PLAYER_MAX_H_VEL: f32 = 1.5;
-/// Max player vertical velocity.
-const PLAYER_MAX_Y_VEL: f32 = 5.;
-/// The positive y velocity added to the player every jump boost tick.
-const PLAYER_JUMP_VEL: f32 = 2.2;
-/// The x velocity added to the player when A/D is held.
-const PLAYER_MOVE_VEL: f32 = 0.6;
-/// The y velocity subtracted from the player due to gravity.
-const PLAYER_GRAVITY: f32 = 0.15;
+use crate::input::action::{tick_just_pressed, InputAction, TickInput};
+use crate::level::{CurrentLevel, LevelSystems};
+use crate::shared::GroupLabel;
+
+use super::{
+    not_input_locked,
+    spawn::{player_collider, player_hurtbox_collider},
+    tuning::MovementTuning,
+    InputLocked, PlayerHurtMarker, PlayerMarker,
+};
+
+/// The distance from the center of the player to the sides of the player's collider.
+const PLAYER_HALF_WIDTH: f32 = 6.0;
 
 pub struct PlayerMovementPlugin;
 
@@ -35,15 +26,26 @@ impl Plugin for PlayerMovementPlugin {
                 .in_set(LevelSystems::Simulation),
         )
         .add_systems(
-            Update,
+            FixedUpdate,
             queue_jump
                 .run_if(not_input_locked)
-                .run_if(jump_key_pressed)
+                .run_if(
+                    tick_just_pressed(InputAction::Jump).or(tick_just_pressed(InputAction::MoveUp)),
+                )
+                .before(move_player)
+                .in_set(LevelSystems::Simulation),
+        )
+        .add_systems(
+            FixedUpdate,
+            queue_dash
+                .run_if(not_input_locked)
+                .run_if(tick_just_pressed(InputAction::Dash))
+                .run_if(dash_allowed)
                 .before(move_player)
                 .in_set(LevelSystems::Simulation),
         )
         .add_systems(
-            Update,
+            FixedUpdate,
             crouch_player
                 .run_if(not_input_locked)
                 .before(move_player)
@@ -52,6 +54,24 @@ impl Plugin for PlayerMovementPlugin {
     }
 }
 
+/// Which side of the player a wall is on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum WallSide {
+    #[default]
+    Left,
+    Right,
+}
+
+impl WallSide {
+    /// The direction pointing from the player to the wall along the x axis.
+    pub fn direction(&self) -> f32 {
+        match self {
+            WallSide::Left => -1.0,
+            WallSide::Right => 1.0,
+        }
+    }
+}
+
 /// [`Component`] that stores information about the player's movement state.
 #[derive(Component, Default)]
 pub struct PlayerMovement {
@@ -59,83 +79,229 @@ pub struct PlayerMovement {
     pub velocity: Vec2,
     pub crouching: bool,
     pub sneaking: bool,
+    /// Velocity added by [`WindZone`](crate::level::wind::WindZone)s this tick
+    pub wind: Vec2,
+    /// Whether the player is inside [`Water`](crate::level::water::Water) this tick
+    pub submerged: bool,
+    /// The side of the wall the player is sliding down, if they are pressing into one while
+    /// falling
+    pub wall_slide: Option<WallSide>,
     should_jump_ticks_remaining: isize,
     coyote_time_ticks_remaining: isize,
     jump_boost_ticks_remaining: isize,
+    wall_coyote_time_ticks_remaining: isize,
+    wall_jump_lockout_ticks_remaining: isize,
+    /// The side of the last wall the player touched, used to kick away from it when wall jumping
+    last_wall_side: WallSide,
+    should_dash: bool,
+    /// Whether the player can dash, recharged by landing
+    dash_charged: bool,
+    dash_ticks_remaining: isize,
+    dash_direction: Vec2,
+    /// The last horizontal direction the player moved in, used when dashing without input
+    facing: f32,
 }
 
-/// [`System`] that is run the frame the space bar is pressed. Allows the player to jump for the
-/// next couple of frames.
-pub fn queue_jump(mut q_player: Query<&mut PlayerMovement, With<PlayerMarker>>) {
+impl PlayerMovement {
+    pub fn is_dashing(&self) -> bool {
+        self.dash_ticks_remaining > 0
+    }
+}
+
+/// [`System`] that is run on the tick the space bar is pressed. Allows the player to jump for the
+/// next couple of ticks.
+pub fn queue_jump(
+    mut q_player: Query<&mut PlayerMovement, With<PlayerMarker>>,
+    tuning: Res<MovementTuning>,
+) {
     let Ok(mut player) = q_player.get_single_mut() else {
         return;
     };
-    player.should_jump_ticks_remaining = SHOULD_JUMP_TICKS;
+    player.should_jump_ticks_remaining = tuning.should_jump_ticks;
 }
 
-/// [`System`] that is run on [`Update`] to crouch player
+/// [`System`] that is run on the tick the dash key is pressed. The dash starts in [`move_player`]
+/// on the same tick if the player has a dash charged.
+pub fn queue_dash(mut q_player: Query<&mut PlayerMovement, With<PlayerMarker>>) {
+    let Ok(mut player) = q_player.get_single_mut() else {
+        return;
+    };
+    player.should_dash = true;
+}
+
+/// [`System`] that is run on [`FixedUpdate`] to crouch the player while the down key is held. The
+/// player's colliders shrink while crouching, and the player will stay crouched while there is a
+/// ceiling above them.
 pub fn crouch_player(
-    // query transform
-    mut q_player: Query<(&mut PlayerMovement, &mut Collider), With<PlayerMarker>>,
-    //ButtonInput<KeyCode> resource (access resource)
-    keys: Res<ButtonInput<KeyCode>>,
-    config: Res<Config>
+    mut q_player: Query<(&mut PlayerMovement, &mut Collider, &Transform), With<PlayerMarker>>,
+    mut q_hurtbox: Query<&mut Collider, (With<PlayerHurtMarker>, Without<PlayerMarker>)>,
+    input: Res<TickInput>,
+    rapier_context: ReadDefaultRapierContext,
+    mut colliders_crouched: Local<bool>,
 ) {
-    // ensure only 1 candidate to match query; let Ok = pattern matching
-    let Ok((mut player, mut _collider)) = q_player.get_single_mut() else {
+    let Ok((mut player, mut collider, transform)) = q_player.get_single_mut() else {
         return;
     };
 
-    // TODO: fix colliders (both player and hurtbox)
-    if keys.just_pressed(config.controls_config.key_down) && !player.crouching {
-        // decrease size by half
+    // diving takes priority over crouching while swimming
+    let wants_crouch = input.actions.pressed(InputAction::MoveDown) && !player.submerged;
+    if wants_crouch && !player.crouching {
         player.crouching = true;
-    }
-    if keys.just_released(config.controls_config.key_down) && player.crouching {
+    } else if !wants_crouch && player.crouching && !ceiling_above(&rapier_context, transform) {
         player.crouching = false;
     }
+
+    // also catches the movement state being reset on respawn
+    if player.crouching != *colliders_crouched {
+        *colliders_crouched = player.crouching;
+        *collider = player_collider(player.crouching);
+        if let Ok(mut hurtbox) = q_hurtbox.get_single_mut() {
+            *hurtbox = player_hurtbox_collider(player.crouching);
+        }
+    }
+}
+
+/// Checks whether there is terrain in the space the top of the player's collider would take up if
+/// they stood up.
+fn ceiling_above(rapier_context: &ReadDefaultRapierContext, player_transform: &Transform) -> bool {
+    // the part of the standing collider that is not covered by the crouching collider, see
+    // player_collider
+    const STAND_CHECK_Y_OFFSET: f32 = 2.0;
+    const STAND_CHECK_HALF_HEIGHT: f32 = 3.0;
+
+    let filter = QueryFilter::new()
+        .exclude_sensors()
+        .groups(CollisionGroups::new(
+            GroupLabel::PLAYER_COLLIDER,
+            GroupLabel::TERRAIN | GroupLabel::PLATFORM,
+        ));
+    rapier_context
+        .cast_shape(
+            player_transform.translation.truncate() + Vec2::new(0.0, STAND_CHECK_Y_OFFSET),
+            0.0,
+            Vec2::Y,
+            &Collider::cuboid(PLAYER_HALF_WIDTH, STAND_CHECK_HALF_HEIGHT),
+            ShapeCastOptions {
+                max_time_of_impact: 0.0,
+                target_distance: 0.0,
+                stop_at_penetration: true,
+                compute_impact_geometry_on_penetration: false,
+            },
+            filter,
+        )
+        .is_some()
 }
 
 /// [`System`] that is run on [`Update`] to move the player around.
+#[allow(clippy::type_complexity)]
 pub fn move_player(
     mut q_player: Query<
         (
             &mut KinematicCharacterController,
             &KinematicCharacterControllerOutput,
             &mut PlayerMovement,
+            &Transform,
             Option<&InputLocked>,
         ),
         With<PlayerMarker>,
     >,
-    keys: Res<ButtonInput<KeyCode>>,
-    config: Res<Config>,
+    input: Res<TickInput>,
+    tuning: Res<MovementTuning>,
+    rapier_context: ReadDefaultRapierContext,
 ) {
-    let Ok((mut controller, output, mut player, movement_locked)) = q_player.get_single_mut()
+    let Ok((mut controller, output, mut player, transform, movement_locked)) =
+        q_player.get_single_mut()
     else {
         return;
     };
 
-    let check_pressed = |key: KeyCode| {
+    let check_pressed = |action: InputAction| {
         if movement_locked.is_some() {
             return false;
         }
-        keys.pressed(key)
+        input.actions.pressed(action)
     };
 
-    if output.grounded {
-        player.coyote_time_ticks_remaining = COYOTE_TIME_TICKS;
+    // Jumping while submerged acts as a swim stroke
+    if output.grounded || player.submerged {
+        player.coyote_time_ticks_remaining = tuning.coyote_time_ticks;
+        player.dash_charged = true;
+    }
+
+    if player.should_dash {
+        player.should_dash = false;
+        if player.dash_charged && !output.grounded && !player.is_dashing() {
+            let mut direction = Vec2::ZERO;
+            if check_pressed(InputAction::MoveLeft) {
+                direction.x -= 1.;
+            }
+            if check_pressed(InputAction::MoveRight) {
+                direction.x += 1.;
+            }
+            if check_pressed(InputAction::MoveUp) {
+                direction.y += 1.;
+            }
+            if check_pressed(InputAction::MoveDown) {
+                direction.y -= 1.;
+            }
+            if direction == Vec2::ZERO {
+                direction.x = if player.facing < 0. { -1. } else { 1. };
+            }
+            player.dash_direction = direction.normalize();
+            player.dash_ticks_remaining = tuning.dash_ticks;
+            player.dash_charged = false;
+            player.jump_boost_ticks_remaining = 0;
+            player.wall_slide = None;
+        }
     }
 
-    // Can only jump if they've pressed space within the past SHOULD_JUMP_TICKS, and they have been
-    // grounded in the past COYOTE_TIME_TICKS
+    // Dashing overrides gravity and input for the duration of the burst
+    if player.is_dashing() {
+        player.dash_ticks_remaining -= 1;
+        player.velocity = player.dash_direction * tuning.dash_vel;
+        if !player.is_dashing() {
+            player.velocity *= tuning.dash_end_vel_mult;
+        }
+        controller.translation = Some(player.velocity);
+        return;
+    }
+
+    let touching_wall = if output.grounded || player.submerged {
+        None
+    } else {
+        find_touching_wall(&rapier_context, transform)
+    };
+    if let Some(wall_side) = touching_wall {
+        player.wall_coyote_time_ticks_remaining = tuning.wall_coyote_time_ticks;
+        player.last_wall_side = wall_side;
+    }
+    player.wall_slide = touching_wall.filter(|wall_side| {
+        let action_towards_wall = match wall_side {
+            WallSide::Left => InputAction::MoveLeft,
+            WallSide::Right => InputAction::MoveRight,
+        };
+        player.velocity.y <= 0. && check_pressed(action_towards_wall)
+    });
+
+    // Can only jump if they've pressed space within the past should_jump_ticks, and they have been
+    // grounded in the past coyote_time_ticks
     if player.should_jump_ticks_remaining > 0 && player.coyote_time_ticks_remaining > 0 {
-        player.jump_boost_ticks_remaining = JUMP_BOOST_TICKS;
-    } else if !check_pressed(config.controls_config.key_jump)
-        && !check_pressed(config.controls_config.key_up)
+        player.jump_boost_ticks_remaining = tuning.jump_boost_ticks;
+    } else if player.should_jump_ticks_remaining > 0 && player.wall_coyote_time_ticks_remaining > 0
+    {
+        // Wall jump, kicking away from the wall
+        player.jump_boost_ticks_remaining = tuning.jump_boost_ticks;
+        player.should_jump_ticks_remaining = 0;
+        player.wall_coyote_time_ticks_remaining = 0;
+        player.wall_jump_lockout_ticks_remaining = tuning.wall_jump_lockout_ticks;
+        player.velocity.x = -player.last_wall_side.direction() * tuning.wall_jump_h_vel;
+        player.wall_slide = None;
+    } else if !check_pressed(InputAction::Jump)
+        && !check_pressed(InputAction::MoveUp)
         && player.velocity.y > 0.
     {
         // Jump was cut
-        player.velocity.y = PLAYER_GRAVITY;
+        player.velocity.y = tuning.gravity;
         player.jump_boost_ticks_remaining = 0;
     } else if output.desired_translation.y > 0. && output.effective_translation.y < 0.05 {
         // Bonked head onto wall
@@ -146,34 +312,61 @@ pub fn move_player(
     }
 
     if player.jump_boost_ticks_remaining > 0 {
-        player.velocity.y = PLAYER_JUMP_VEL;
+        player.velocity.y = tuning.jump_vel;
+    } else if player.submerged {
+        player.velocity.y -= tuning.gravity * tuning.water_gravity_mult;
+        if check_pressed(InputAction::MoveDown) {
+            player.velocity.y -= tuning.dive_vel;
+        }
     } else {
-        player.velocity.y -= PLAYER_GRAVITY;
+        player.velocity.y -= tuning.gravity;
     }
+    // wind is added before clamping on both axes, so it can't push the player past the max
+    // velocity
+    player.velocity.y += player.wind.y;
 
-    player.velocity.y = player.velocity.y.clamp(-PLAYER_MAX_Y_VEL, PLAYER_MAX_Y_VEL);
+    let max_y_vel = if player.submerged {
+        tuning.water_max_y_vel
+    } else {
+        tuning.max_y_vel
+    };
+    player.velocity.y = player.velocity.y.clamp(-max_y_vel, max_y_vel);
+    if player.wall_slide.is_some() {
+        player.velocity.y = player.velocity.y.max(-tuning.wall_slide_max_fall_vel);
+    }
 
-    let mut moved = false;
-    if check_pressed(config.controls_config.key_left) {
-        player.velocity.x -= PLAYER_MOVE_VEL;
+    // the kick from a wall jump should carry the player for a bit, ignoring steering and friction
+    let wall_jump_locked = player.wall_jump_lockout_ticks_remaining > 0;
+    let mut moved = wall_jump_locked;
+    if !wall_jump_locked && check_pressed(InputAction::MoveLeft) {
+        player.velocity.x -= tuning.move_vel;
+        player.facing = -1.;
         moved = true;
     }
-    if check_pressed(config.controls_config.key_right) {
-        player.velocity.x += PLAYER_MOVE_VEL;
+    if !wall_jump_locked && check_pressed(InputAction::MoveRight) {
+        player.velocity.x += tuning.move_vel;
+        player.facing = 1.;
         moved = true;
     }
+    player.velocity.x += player.wind.x;
 
-    player.sneaking = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
-    let temp_max_h_vel = if player.sneaking {
-        PLAYER_MAX_H_VEL / 2.
+    player.sneaking = input.actions.pressed(InputAction::Sneak);
+    let mut temp_max_h_vel = if player.sneaking {
+        tuning.max_h_vel * tuning.sneak_max_h_vel_mult
     } else {
-        PLAYER_MAX_H_VEL
+        tuning.max_h_vel
     };
+    if player.submerged {
+        temp_max_h_vel *= tuning.water_max_h_vel_mult;
+    }
+    if player.crouching && output.grounded {
+        temp_max_h_vel *= tuning.crouch_max_h_vel_mult;
+    }
     player.velocity.x = player.velocity.x.clamp(-temp_max_h_vel, temp_max_h_vel);
     if !moved {
         // slow player down when not moving horizontally
         // NOTE: why not using rapier friction?
-        player.velocity.x *= 0.6;
+        player.velocity.x *= tuning.friction;
         if player.velocity.x.abs() < 0.1 {
             player.velocity.x = 0.;
         }
@@ -182,14 +375,55 @@ pub fn move_player(
     player.should_jump_ticks_remaining -= 1;
     player.jump_boost_ticks_remaining -= 1;
     player.coyote_time_ticks_remaining -= 1;
+    player.wall_coyote_time_ticks_remaining -= 1;
+    player.wall_jump_lockout_ticks_remaining -= 1;
 
     controller.translation = Some(player.velocity);
 }
 
-fn jump_key_pressed(
-    keys: Res<ButtonInput<KeyCode>>,
-    config: Res<Config>,
-) -> bool {
-    return keys.just_pressed(config.controls_config.key_jump) || 
-    keys.just_pressed(config.controls_config.key_up);
+/// Casts thin shapes against both sides of the player's collider to find a wall to slide on.
+/// Sensors and hazards are ignored, so only solid terrain counts as a wall.
+fn find_touching_wall(
+    rapier_context: &ReadDefaultRapierContext,
+    player_transform: &Transform,
+) -> Option<WallSide> {
+    // matches the offset of the player's collider and the character controller
+    const WALL_CHECK_Y_OFFSET: f32 = -2.0;
+    const WALL_CHECK_HALF_HEIGHT: f32 = 5.0;
+    const WALL_CHECK_DISTANCE: f32 = 1.5;
+
+    let filter = QueryFilter::new()
+        .exclude_sensors()
+        .groups(CollisionGroups::new(
+            GroupLabel::PLAYER_COLLIDER,
+            GroupLabel::TERRAIN,
+        ));
+    [WallSide::Left, WallSide::Right]
+        .into_iter()
+        .find(|wall_side| {
+            let shape_pos = player_transform.translation.truncate()
+                + Vec2::new(
+                    wall_side.direction() * (PLAYER_HALF_WIDTH + WALL_CHECK_DISTANCE / 2.0),
+                    WALL_CHECK_Y_OFFSET,
+                );
+            rapier_context
+                .cast_shape(
+                    shape_pos,
+                    0.0,
+                    Vec2::new(wall_side.direction(), 0.0),
+                    &Collider::cuboid(WALL_CHECK_DISTANCE / 2.0, WALL_CHECK_HALF_HEIGHT),
+                    ShapeCastOptions {
+                        max_time_of_impact: 0.0,
+                        target_distance: 0.0,
+                        stop_at_penetration: true,
+                        compute_impact_geometry_on_penetration: false,
+                    },
+                    filter,
+                )
+                .is_some()
+        })
+}
+
+fn dash_allowed(current_level: Res<CurrentLevel>) -> bool {
+    current_level.allow_dash
 }
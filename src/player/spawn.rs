@@ -13,6 +13,27 @@ use super::{
     PlayerBundle, PlayerHurtMarker, PlayerMarker,
 };
 
+/// The [`Collider`] used by the player's [`KinematicCharacterController`]. Crouching keeps the
+/// bottom of the collider in place and lowers the top.
+pub fn player_collider(crouching: bool) -> Collider {
+    let (offset, half_height) = if crouching { (-5.0, 4.0) } else { (-2.0, 7.0) };
+    Collider::compound(vec![(
+        Vect::new(0.0, offset),
+        Rot::default(),
+        Collider::cuboid(6.0, half_height),
+    )])
+}
+
+/// The [`Collider`] of the player's hurtbox, see [`PlayerHurtMarker`].
+pub fn player_hurtbox_collider(crouching: bool) -> Collider {
+    let (offset, half_height) = if crouching { (-4.0, 3.0) } else { (-2.0, 5.0) };
+    Collider::compound(vec![(
+        Vect::new(0.0, offset),
+        Rot::default(),
+        Collider::cuboid(4.0, half_height),
+    )])
+}
+
 /// Used by Ldtk to spawn the player correctly with all of the correct [`Component`]s.
 pub fn init_player_bundle(_: &EntityInstance) -> PlayerBundle {
     PlayerBundle {
@@ -26,11 +47,7 @@ pub fn init_player_bundle(_: &EntityInstance) -> PlayerBundle {
             ..default()
         },
         controller_output: KinematicCharacterControllerOutput::default(),
-        collider: Collider::compound(vec![(
-            Vect::new(0.0, -2.0),
-            Rot::default(),
-            Collider::cuboid(6.0, 7.0),
-        )]),
+        collider: player_collider(false),
         collision_groups: CollisionGroups::new(
             GroupLabel::PLAYER_COLLIDER,
             GroupLabel::TERRAIN | GroupLabel::PLATFORM,
@@ -84,11 +101,7 @@ pub fn add_player_sensors(
 
     commands.entity(player).with_children(|parent| {
         parent
-            .spawn(Collider::compound(vec![(
-                Vect::new(0.0, -2.0),
-                Rot::default(),
-                Collider::cuboid(4.0, 5.0),
-            )]))
+            .spawn(player_hurtbox_collider(false))
             .insert(Sensor)
             .insert(RigidBody::Dynamic)
             .insert(GravityScale(0.0))
@@ -4,8 +4,15 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    animation::AnimationConfig, camera::HIGHRES_LAYER, level::LevelSystems,
-    player::match_player::MatchPlayerPixel, shared::GroupLabel,
+    animation::AnimationConfig,
+    camera::HIGHRES_LAYER,
+    level::{
+        wind::{wind_zone_force_at, WindZone},
+        LevelSystems,
+    },
+    particle::Wind,
+    player::match_player::MatchPlayerPixel,
+    shared::GroupLabel,
 };
 
 use super::{
@@ -15,6 +22,9 @@ use super::{
     PlayerMarker,
 };
 
+/// Scales the force of [`WindZone`]s before it is added to the acceleration of [`Strand`]s.
+const WIND_ZONE_STRAND_MULT: f32 = 0.5;
+
 pub struct PlayerStrandPlugin;
 
 impl Plugin for PlayerStrandPlugin {
@@ -92,6 +102,9 @@ pub fn update_strand(
     mut q_strand: Query<(Entity, &mut Strand)>,
     mut q_transforms: Query<&mut Transform>,
     rapier_context: ReadDefaultRapierContext,
+    q_wind_zones: Query<(&WindZone, &GlobalTransform)>,
+    wind: Res<Wind>,
+    time: Res<Time>,
 ) {
     let mut strands = q_strand.iter_mut().collect::<Vec<_>>();
     strands.sort_by(|(_, a), (_, b)| a.priority.cmp(&b.priority));
@@ -108,7 +121,8 @@ pub fn update_strand(
 
         strand.last_pos = pos;
 
-        let acceleration = Vec2::new(0.0, -strand.gravity);
+        let wind_force = wind_zone_force_at(&q_wind_zones, &wind, time.elapsed_secs(), pos);
+        let acceleration = Vec2::new(0.0, -strand.gravity) + wind_force * WIND_ZONE_STRAND_MULT;
         pos += velocity + acceleration;
 
         if strand.physics {
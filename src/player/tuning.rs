@@ -0,0 +1,321 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::replay::live_input,
+    settings::{SettingName, Settings},
+};
+
+/// The path of the [`MovementTuningPresets`] asset, relative to the assets folder. Changes to this
+/// file are picked up while the game is running on native builds.
+const MOVEMENT_TUNING_PATH: &str = "movement.tuning.toml";
+
+/// The preset that every other preset inherits unspecified values from.
+pub const DEFAULT_PRESET: &str = "default";
+
+/// [`Plugin`] that loads the [`MovementTuning`] used by
+/// [`move_player`](super::movement::move_player) from [`MOVEMENT_TUNING_PATH`].
+pub struct MovementTuningPlugin;
+
+impl Plugin for MovementTuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<MovementTuningPresets>()
+            .init_asset_loader::<MovementTuningLoader>()
+            .init_resource::<MovementTuning>()
+            .add_systems(Startup, load_movement_tuning)
+            .add_systems(
+                Update,
+                apply_movement_tuning
+                    .run_if(
+                        on_event::<AssetEvent<MovementTuningPresets>>
+                            .or(resource_changed::<Settings>),
+                    )
+                    .run_if(live_input),
+            );
+    }
+}
+
+/// [`Resource`] holding every tunable value used to move the player. The defaults are used until
+/// the [`MovementTuningPresets`] asset has loaded, and while a replay is running they are the
+/// ones the [`Recording`](crate::input::replay::Recording) was made with.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MovementTuning {
+    /// The number of [`FixedUpdate`] steps the player can jump for after pressing the spacebar.
+    pub should_jump_ticks: isize,
+    /// The number of [`FixedUpdate`] steps the player can jump for after falling off an edge.
+    pub coyote_time_ticks: isize,
+    /// The number of [`FixedUpdate`] steps the player should receive upward velocity for.
+    pub jump_boost_ticks: isize,
+
+    /// Max player horizontal velocity.
+    pub max_h_vel: f32,
+    /// Max player vertical velocity.
+    pub max_y_vel: f32,
+    /// The positive y velocity added to the player every jump boost tick.
+    pub jump_vel: f32,
+    /// The x velocity added to the player when A/D is held.
+    pub move_vel: f32,
+    /// The y velocity subtracted from the player due to gravity.
+    pub gravity: f32,
+    /// Multiplier applied to the player's x velocity every tick they aren't moving horizontally.
+    pub friction: f32,
+    /// Multiplier applied to [`MovementTuning::max_h_vel`] while sneaking.
+    pub sneak_max_h_vel_mult: f32,
+
+    /// The number of [`FixedUpdate`] steps the player can wall jump for after letting go of a wall.
+    pub wall_coyote_time_ticks: isize,
+    /// The number of [`FixedUpdate`] steps horizontal input is ignored for after a wall jump, so
+    /// the player can't immediately steer back into the wall.
+    pub wall_jump_lockout_ticks: isize,
+    /// The x velocity the player is kicked away from the wall with when wall jumping.
+    pub wall_jump_h_vel: f32,
+    /// Max player falling velocity while sliding down a wall.
+    pub wall_slide_max_fall_vel: f32,
+
+    /// Multiplier applied to [`MovementTuning::gravity`] while the player is submerged in
+    /// [`Water`](crate::level::water::Water).
+    pub water_gravity_mult: f32,
+    /// Max player vertical velocity while submerged.
+    pub water_max_y_vel: f32,
+    /// Multiplier applied to [`MovementTuning::max_h_vel`] while submerged.
+    pub water_max_h_vel_mult: f32,
+    /// The negative y velocity added to the player every tick the down key is held while
+    /// submerged.
+    pub dive_vel: f32,
+
+    /// Multiplier applied to [`MovementTuning::max_h_vel`] while crouch-walking.
+    pub crouch_max_h_vel_mult: f32,
+
+    /// The number of [`FixedUpdate`] steps a dash lasts for.
+    pub dash_ticks: isize,
+    /// The speed of the player while dashing.
+    pub dash_vel: f32,
+    /// Multiplier applied to the player's velocity when a dash ends, so the player doesn't shoot
+    /// off.
+    pub dash_end_vel_mult: f32,
+}
+
+impl Default for MovementTuning {
+    fn default() -> Self {
+        MovementTuning {
+            should_jump_ticks: 8,
+            coyote_time_ticks: 5,
+            jump_boost_ticks: 2,
+            max_h_vel: 1.5,
+            max_y_vel: 5.,
+            jump_vel: 2.2,
+            move_vel: 0.6,
+            gravity: 0.15,
+            friction: 0.6,
+            sneak_max_h_vel_mult: 0.5,
+            wall_coyote_time_ticks: 4,
+            wall_jump_lockout_ticks: 8,
+            wall_jump_h_vel: 1.5,
+            wall_slide_max_fall_vel: 0.8,
+            water_gravity_mult: 0.3,
+            water_max_y_vel: 1.5,
+            water_max_h_vel_mult: 0.7,
+            dive_vel: 0.1,
+            crouch_max_h_vel_mult: 0.5,
+            dash_ticks: 8,
+            dash_vel: 3.0,
+            dash_end_vel_mult: 0.4,
+        }
+    }
+}
+
+/// [`Asset`] containing every named [`MovementTuning`] preset, with the [`DEFAULT_PRESET`] first.
+#[derive(Asset, TypePath, Debug)]
+pub struct MovementTuningPresets(Vec<(String, MovementTuning)>);
+
+impl MovementTuningPresets {
+    fn get(&self, name: &str) -> Option<&MovementTuning> {
+        self.0
+            .iter()
+            .find(|(preset_name, _)| preset_name == name)
+            .map(|(_, tuning)| tuning)
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.0.iter().map(|(name, _)| name.clone()).collect()
+    }
+}
+
+/// Parses the presets from a TOML file where every top level table is a preset. Values missing
+/// from a preset are taken from the [`DEFAULT_PRESET`], and then from [`MovementTuning::default`].
+fn parse_presets(contents: &str) -> Result<MovementTuningPresets, MovementTuningLoaderError> {
+    let table: toml::Table = toml::from_str(contents)?;
+    let base = match table.get(DEFAULT_PRESET) {
+        Some(toml::Value::Table(base)) => base.clone(),
+        Some(_) => {
+            return Err(MovementTuningLoaderError::NotATable(
+                DEFAULT_PRESET.to_owned(),
+            ))
+        }
+        None => toml::Table::new(),
+    };
+
+    let mut presets = vec![(
+        DEFAULT_PRESET.to_owned(),
+        toml::Value::Table(base.clone()).try_into()?,
+    )];
+    for (name, value) in table {
+        if name == DEFAULT_PRESET {
+            continue;
+        }
+        let toml::Value::Table(overrides) = value else {
+            return Err(MovementTuningLoaderError::NotATable(name));
+        };
+        let mut merged = base.clone();
+        merged.extend(overrides);
+        presets.push((name, toml::Value::Table(merged).try_into()?));
+    }
+    Ok(MovementTuningPresets(presets))
+}
+
+#[derive(Debug)]
+pub enum MovementTuningLoaderError {
+    Io(std::io::Error),
+    Utf8(std::str::Utf8Error),
+    Toml(toml::de::Error),
+    NotATable(String),
+}
+
+impl fmt::Display for MovementTuningLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read movement tuning: {err}"),
+            Self::Utf8(err) => write!(f, "movement tuning is not valid UTF-8: {err}"),
+            Self::Toml(err) => write!(f, "could not parse movement tuning: {err}"),
+            Self::NotATable(name) => write!(f, "movement tuning preset {name} is not a table"),
+        }
+    }
+}
+
+impl std::error::Error for MovementTuningLoaderError {}
+
+impl From<std::io::Error> for MovementTuningLoaderError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<std::str::Utf8Error> for MovementTuningLoaderError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        Self::Utf8(err)
+    }
+}
+
+impl From<toml::de::Error> for MovementTuningLoaderError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Toml(err)
+    }
+}
+
+#[derive(Default)]
+pub struct MovementTuningLoader;
+
+impl AssetLoader for MovementTuningLoader {
+    type Asset = MovementTuningPresets;
+    type Settings = ();
+    type Error = MovementTuningLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        parse_presets(std::str::from_utf8(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.toml"]
+    }
+}
+
+/// [`Resource`] keeping the [`MovementTuningPresets`] loaded, so they can be hot reloaded.
+#[derive(Resource)]
+struct MovementTuningHandle(Handle<MovementTuningPresets>);
+
+fn load_movement_tuning(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(MovementTuningHandle(
+        asset_server.load(MOVEMENT_TUNING_PATH),
+    ));
+}
+
+/// [`System`] that copies the preset chosen in the [`Settings`] into the [`MovementTuning`]
+/// whenever the presets are (re)loaded or the chosen preset changes.
+fn apply_movement_tuning(
+    mut ev_asset: EventReader<AssetEvent<MovementTuningPresets>>,
+    tuning_handle: Option<Res<MovementTuningHandle>>,
+    presets: Res<Assets<MovementTuningPresets>>,
+    mut settings: ResMut<Settings>,
+    mut tuning: ResMut<MovementTuning>,
+) {
+    let Some(tuning_handle) = tuning_handle else {
+        return;
+    };
+    let Some(presets) = presets.get(&tuning_handle.0) else {
+        return;
+    };
+
+    let reloaded = ev_asset.read().any(|ev| {
+        ev.is_loaded_with_dependencies(&tuning_handle.0) || ev.is_modified(&tuning_handle.0)
+    });
+    if reloaded {
+        settings.set_choice_options(SettingName::MovementPreset, presets.names());
+    }
+
+    let preset = settings
+        .choice(SettingName::MovementPreset)
+        .unwrap_or(DEFAULT_PRESET);
+    let Some(new_tuning) = presets.get(preset) else {
+        warn!("Movement tuning preset {preset} does not exist");
+        return;
+    };
+    tuning.set_if_neq(new_tuning.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_inherit_from_default() {
+        let presets = parse_presets(
+            r#"
+            [assist]
+            coyote_time_ticks = 10
+
+            [default]
+            gravity = 0.2
+            coyote_time_ticks = 6
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(presets.names(), vec!["default", "assist"]);
+        let default = presets.get(DEFAULT_PRESET).unwrap();
+        assert_eq!(default.gravity, 0.2);
+        assert_eq!(default.coyote_time_ticks, 6);
+        assert_eq!(default.max_h_vel, MovementTuning::default().max_h_vel);
+
+        let assist = presets.get("assist").unwrap();
+        assert_eq!(assist.gravity, 0.2);
+        assert_eq!(assist.coyote_time_ticks, 10);
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert!(parse_presets("[default]\ngravty = 0.2").is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use std::{collections::BTreeMap, fmt, io};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelIid;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    level::{
+        level_completion::{handle_start_end_markers, LevelCompletionEvent},
+        CurrentLevel,
+    },
+    level_pack::LevelPacks,
+    level_select::Levels,
+    shared::GameState,
+    speedrun::handle_speedrun_splits,
+};
+
+/// The version of the [`SaveData`] written by this build. Bump this and add a migration to
+/// [`MIGRATIONS`] whenever the format changes.
+const SAVE_VERSION: u32 = 1;
+
+/// Migrations from every old version of the save file to the next one, so `MIGRATIONS[0]` migrates
+/// version 1 to version 2.
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[];
+
+/// The number of save slots the player can pick from on the start menu.
+pub const SAVE_SLOTS: usize = 3;
+
+fn slot_file_name(slot: usize) -> String {
+    format!("save_{}.toml", slot + 1)
+}
+
+/// [`Plugin`] that saves the player's progress into the [`ActiveSaveSlot`] whenever they complete a
+/// level and when the game exits. Saves are stored in the platform data directory, or in
+/// `localStorage` on the web.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveData>()
+            .init_resource::<ActiveSaveSlot>()
+            .add_systems(
+                Update,
+                (
+                    save_progress
+                        .after(handle_start_end_markers)
+                        .after(handle_speedrun_splits)
+                        .run_if(on_event::<LevelCompletionEvent>),
+                    track_play_time
+                        .run_if(in_state(GameState::Playing).or(in_state(GameState::Animating))),
+                    track_last_level.run_if(resource_changed::<CurrentLevel>),
+                ),
+            )
+            .add_systems(Last, save_on_exit.run_if(on_event::<AppExit>));
+    }
+}
+
+/// [`Resource`] holding the save slot picked on the start menu. Nothing is saved while this is
+/// [`None`], e.g. when a replay skips the start menu.
+#[derive(Resource, Default, Debug)]
+pub struct ActiveSaveSlot(pub Option<usize>);
+
+/// [`Resource`] holding everything that is saved between sessions.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveData {
+    pub version: u32,
+    /// The progress of every level, by the path of its
+    /// [`LevelPack`](crate::level_pack::LevelPack) and then by
+    /// [`LevelIid`](bevy_ecs_ldtk::LevelIid). Packs made from copies of each other share level
+    /// iids.
+    #[serde(default)]
+    pub levels: BTreeMap<String, BTreeMap<String, LevelProgress>>,
+    /// The time spent playing on this save, in seconds.
+    #[serde(default)]
+    pub play_time_secs: f64,
+    /// The `LevelId` of the level the player was last in.
+    #[serde(default)]
+    pub last_level: Option<String>,
+    /// The fewest [`FixedUpdate`] steps each level has been completed in, by level pack and level
+    /// like [`SaveData::levels`].
+    #[serde(default)]
+    pub personal_bests: BTreeMap<String, BTreeMap<String, u32>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LevelProgress {
+    pub complete: bool,
+    pub locked: bool,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        SaveData {
+            version: SAVE_VERSION,
+            levels: BTreeMap::new(),
+            play_time_secs: 0.0,
+            last_level: None,
+            personal_bests: BTreeMap::new(),
+        }
+    }
+}
+
+impl SaveData {
+    /// Loads the save in a slot, or [`None`] if the slot is empty.
+    pub fn load(slot: usize) -> Result<Option<Self>, SaveError> {
+        storage::read(&slot_file_name(slot))?
+            .map(|contents| SaveData::parse(&contents))
+            .transpose()
+    }
+
+    /// Writes the save into a slot, overwriting what was there.
+    pub fn write(&self, slot: usize) -> Result<(), SaveError> {
+        storage::write(&slot_file_name(slot), &toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn delete(slot: usize) -> Result<(), SaveError> {
+        storage::remove(&slot_file_name(slot))?;
+        Ok(())
+    }
+
+    /// The personal best of a level in the level pack at `pack_path`, in [`FixedUpdate`] steps.
+    pub fn personal_best(&self, pack_path: &str, level_iid: &LevelIid) -> Option<u32> {
+        self.personal_bests
+            .get(pack_path)?
+            .get(level_iid.as_str())
+            .copied()
+    }
+
+    /// How much of the game has been completed over every level pack, from 0 to 1.
+    pub fn completion(&self) -> f32 {
+        let progress: Vec<_> = self.levels.values().flat_map(BTreeMap::values).collect();
+        if progress.is_empty() {
+            return 0.0;
+        }
+        let complete = progress.iter().filter(|progress| progress.complete).count();
+        complete as f32 / progress.len() as f32
+    }
+
+    /// Parses a save file, migrating it from older versions if needed.
+    pub fn parse(contents: &str) -> Result<Self, SaveError> {
+        let mut table: toml::Table = toml::from_str(contents)?;
+        let version = table
+            .get("version")
+            .and_then(toml::Value::as_integer)
+            .ok_or(SaveError::MissingVersion)?;
+        if version < 1 || version > SAVE_VERSION as i64 {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+
+        for migration in &MIGRATIONS[version as usize - 1..] {
+            migration(&mut table);
+        }
+        table.insert("version".into(), toml::Value::Integer(SAVE_VERSION.into()));
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    MissingVersion,
+    UnsupportedVersion(i64),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not access save file: {err}"),
+            Self::Parse(err) => write!(f, "could not parse save file: {err}"),
+            Self::Serialize(err) => write!(f, "could not serialize save file: {err}"),
+            Self::MissingVersion => write!(f, "save file has no version"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "save file version {version} is not supported")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for SaveError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<toml::ser::Error> for SaveError {
+    fn from(err: toml::ser::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+fn write_active_slot(save_data: &SaveData, active_slot: &ActiveSaveSlot) {
+    let Some(slot) = active_slot.0 else {
+        return;
+    };
+    if let Err(err) = save_data.write(slot) {
+        error!("Could not save progress: {err}");
+    }
+}
+
+/// [`System`] that writes the progress of every level to the save file when a level is completed.
+pub fn save_progress(
+    mut ev_completion: EventReader<LevelCompletionEvent>,
+    levels: Res<Levels>,
+    level_packs: Res<LevelPacks>,
+    config: Res<Config>,
+    active_slot: Res<ActiveSaveSlot>,
+    mut save_data: ResMut<SaveData>,
+) {
+    let completed = ev_completion
+        .read()
+        .any(|ev| matches!(ev, LevelCompletionEvent::Completed(_)));
+    // unlocking every level for debugging shouldn't unlock them for good
+    if !completed || config.debug_config.unlock_levels {
+        return;
+    }
+    let pack_progress = save_data
+        .levels
+        .entry(level_packs.active().path.clone())
+        .or_default();
+    for level in levels.0.iter() {
+        pack_progress.insert(
+            level.level_iid.as_str().to_owned(),
+            LevelProgress {
+                complete: level.complete,
+                locked: level.locked,
+            },
+        );
+    }
+    write_active_slot(&save_data, &active_slot);
+}
+
+fn save_on_exit(save_data: Res<SaveData>, active_slot: Res<ActiveSaveSlot>) {
+    write_active_slot(&save_data, &active_slot);
+}
+
+fn track_play_time(time: Res<Time<Real>>, mut save_data: ResMut<SaveData>) {
+    save_data.play_time_secs += time.delta_secs_f64();
+}
+
+fn track_last_level(
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    mut save_data: ResMut<SaveData>,
+) {
+    let Some(level) = levels
+        .0
+        .iter()
+        .find(|level| level.level_iid == current_level.level_iid)
+    else {
+        return;
+    };
+    save_data.last_level = Some(level.level_id.clone());
+}
+
+/// Reading and writing named save files in the platform data directory.
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub mod storage {
+    use std::{fs, io, path::PathBuf};
+
+    fn path(name: &str) -> io::Result<PathBuf> {
+        let Some(data_dir) = dirs::data_dir() else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "could not find the data directory",
+            ));
+        };
+        Ok(data_dir.join("lightborne").join(name))
+    }
+
+    pub fn read(name: &str) -> io::Result<Option<String>> {
+        match fs::read_to_string(path(name)?) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn write(name: &str, contents: &str) -> io::Result<()> {
+        let path = path(name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // write to a temporary file first, so quitting halfway through can't corrupt the save
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(temp_path, path)
+    }
+
+    pub fn remove(name: &str) -> io::Result<()> {
+        match fs::remove_file(path(name)?) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Reading and writing named save files in the browser's `localStorage`.
+#[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
+pub mod storage {
+    use std::io;
+
+    fn local_storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::Unsupported, "localStorage is not available")
+            })
+    }
+
+    fn key(name: &str) -> String {
+        format!("lightborne/{name}")
+    }
+
+    pub fn read(name: &str) -> io::Result<Option<String>> {
+        local_storage()?
+            .get_item(&key(name))
+            .map_err(|_| io::Error::other("could not read from localStorage"))
+    }
+
+    pub fn write(name: &str, contents: &str) -> io::Result<()> {
+        local_storage()?
+            .set_item(&key(name), contents)
+            .map_err(|_| io::Error::other("could not write to localStorage"))
+    }
+
+    pub fn remove(name: &str) -> io::Result<()> {
+        local_storage()?
+            .remove_item(&key(name))
+            .map_err(|_| io::Error::other("could not remove from localStorage"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_data_round_trips() {
+        let mut save_data = SaveData::default();
+        save_data
+            .levels
+            .entry("levels/a.ldtk".to_owned())
+            .or_default()
+            .insert(
+                "a3b2c1".to_owned(),
+                LevelProgress {
+                    complete: true,
+                    locked: false,
+                },
+            );
+        save_data
+            .personal_bests
+            .entry("levels/a.ldtk".to_owned())
+            .or_default()
+            .insert("a3b2c1".to_owned(), 1234);
+        let serialized = toml::to_string(&save_data).unwrap();
+        assert_eq!(SaveData::parse(&serialized).unwrap(), save_data);
+    }
+
+    #[test]
+    fn every_old_version_has_a_migration() {
+        assert_eq!(MIGRATIONS.len(), SAVE_VERSION as usize - 1);
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        assert!(matches!(
+            SaveData::parse("levels = {}"),
+            Err(SaveError::MissingVersion)
+        ));
+        assert!(matches!(
+            SaveData::parse(&format!("version = {}", SAVE_VERSION + 1)),
+            Err(SaveError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -4,11 +4,26 @@ use bevy::audio::Volume;
 use bevy::input::common_conditions::input_just_pressed;
 use bevy::prelude::*;
 use enum_map::{enum_map, Enum, EnumMap};
+use serde::{Deserialize, Serialize};
 
 use crate::camera::handle_move_camera;
+use crate::cli::CliArgs;
+use crate::config::{Config, StoredBindings};
+use crate::input::action::{InputAction, InputBinding, InputBindings, InputRebinding};
+use crate::player::tuning::DEFAULT_PRESET;
+use crate::save::storage;
 use crate::shared::{GameState, UiState};
 use crate::sound::{BgmTrack, ChangeBgmEvent};
 
+/// The file the [`Settings`] are stored in, next to the save files.
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+
+/// The number of angles the light beam snaps to for every option of [`SettingName::SnapAngles`],
+/// or [`None`] to aim freely.
+const SNAP_ANGLES_OPTIONS: [Option<i32>; 4] = [Some(8), Some(16), Some(32), None];
+/// Whether aim assist is on for every option of [`SettingName::AimAssist`].
+const AIM_ASSIST_OPTIONS: [(bool, &str); 2] = [(false, "Off"), (true, "On")];
+
 pub struct SettingsPlugin;
 
 #[derive(Component)]
@@ -40,6 +55,10 @@ pub enum SettingVariant {
         range: RangeInclusive<f32>,
         unit: String,
     },
+    Choice {
+        value: SettingValue<usize>,
+        options: Vec<String>,
+    },
 }
 
 impl Setting {
@@ -53,17 +72,160 @@ impl Setting {
             },
         }
     }
+
+    fn new_choice(name: String, value: usize, options: Vec<String>) -> Self {
+        Self {
+            name,
+            variant: SettingVariant::Choice {
+                value: SettingValue::from_default(value),
+                options,
+            },
+        }
+    }
 }
 
 #[derive(Resource)]
 pub struct Settings(EnumMap<SettingName, Setting>);
 
+impl Settings {
+    /// The currently selected option of a [`SettingVariant::Choice`].
+    pub fn choice(&self, setting_name: SettingName) -> Option<&str> {
+        let SettingVariant::Choice { value, options } = &self.0[setting_name].variant else {
+            return None;
+        };
+        options.get(value.value).map(String::as_str)
+    }
+
+    /// The index of the currently selected option of a [`SettingVariant::Choice`].
+    fn choice_index(&self, setting_name: SettingName) -> Option<usize> {
+        let SettingVariant::Choice { value, .. } = &self.0[setting_name].variant else {
+            return None;
+        };
+        Some(value.value)
+    }
+
+    /// The number of angle increments the light beam snaps to while
+    /// [`InputAction::SnapAim`] is held, or [`None`] if snapping is turned off.
+    pub fn snap_increments(&self) -> Option<i32> {
+        self.choice_index(SettingName::SnapAngles)
+            .and_then(|index| SNAP_ANGLES_OPTIONS.get(index).copied().flatten())
+    }
+
+    /// Whether the light beam is nudged toward light sensors while aiming.
+    pub fn aim_assist(&self) -> bool {
+        self.choice_index(SettingName::AimAssist)
+            .and_then(|index| AIM_ASSIST_OPTIONS.get(index))
+            .is_some_and(|(on, _)| *on)
+    }
+
+    /// Replaces the options of a [`SettingVariant::Choice`], keeping the current selection if it
+    /// is still an option.
+    pub fn set_choice_options(&mut self, setting_name: SettingName, new_options: Vec<String>) {
+        let SettingVariant::Choice { value, options } = &mut self.0[setting_name].variant else {
+            return;
+        };
+        let selected = options.get(value.value);
+        value.value = selected
+            .and_then(|selected| new_options.iter().position(|option| option == selected))
+            .unwrap_or(0);
+        *options = new_options;
+    }
+
+    /// Applies the values from a settings file. Values that are out of range, aren't an option or
+    /// belong to unknown settings are skipped with a warning.
+    fn apply_stored(&mut self, stored: &toml::Table) {
+        for (key, stored_value) in stored {
+            let Ok(setting_name) = toml::Value::String(key.clone()).try_into::<SettingName>()
+            else {
+                warn!("Ignoring unknown setting {key}");
+                continue;
+            };
+            match &mut self.0[setting_name].variant {
+                SettingVariant::Slider { value, range, .. } => {
+                    let stored_value = match stored_value {
+                        toml::Value::Float(float) => *float as f32,
+                        toml::Value::Integer(integer) => *integer as f32,
+                        _ => {
+                            warn!("Ignoring setting {key}, which should be a number");
+                            continue;
+                        }
+                    };
+                    if !range.contains(&stored_value) {
+                        warn!("Ignoring setting {key}, {stored_value} is out of range {range:?}");
+                        continue;
+                    }
+                    value.value = stored_value;
+                }
+                SettingVariant::Choice { value, options } => {
+                    let Some(stored_value) = stored_value.as_str() else {
+                        warn!("Ignoring setting {key}, which should be a string");
+                        continue;
+                    };
+                    if let Some(index) = options.iter().position(|option| option == stored_value) {
+                        value.value = index;
+                    } else if setting_name == SettingName::MovementPreset {
+                        // the presets are only known once the movement tuning has loaded, which
+                        // drops this choice if it turns out not to exist
+                        options.push(stored_value.to_owned());
+                        value.value = options.len() - 1;
+                    } else {
+                        warn!("Ignoring setting {key}, {stored_value} is not an option");
+                    }
+                }
+            }
+        }
+    }
+
+    fn to_stored(&self) -> toml::Table {
+        self.0
+            .iter()
+            .filter_map(|(setting_name, setting)| {
+                let key = toml::Value::try_from(setting_name)
+                    .ok()?
+                    .as_str()?
+                    .to_owned();
+                let value = match &setting.variant {
+                    SettingVariant::Slider { value, .. } => toml::Value::Float(value.value.into()),
+                    SettingVariant::Choice { value, options } => {
+                        toml::Value::String(options.get(value.value)?.clone())
+                    }
+                };
+                Some((key, value))
+            })
+            .collect()
+    }
+
+    /// Writes the settings to [`SETTINGS_FILE_NAME`].
+    fn write(&self) {
+        let contents = match toml::to_string(&self.to_stored()) {
+            Ok(contents) => contents,
+            Err(err) => {
+                error!("Could not serialize settings: {err}");
+                return;
+            }
+        };
+        if let Err(err) = storage::write(SETTINGS_FILE_NAME, &contents) {
+            error!("Could not save settings: {err}");
+        }
+    }
+}
+
 #[derive(Component, Debug, Clone, PartialEq, Eq, Copy)]
 pub struct SettingsIndex(usize);
 
 #[derive(Component, Debug, Clone)]
 pub struct SliderButton(f32);
 
+#[derive(Component, Debug, Clone)]
+pub struct ChoiceButton(isize);
+
+/// Button that starts rebinding an [`InputAction`], see [`InputRebinding`].
+#[derive(Component, Debug, Clone)]
+pub struct RebindButton(InputAction);
+
+#[derive(Component, Debug, Clone)]
+pub struct ResetBindingsButton;
+
 #[derive(Component)]
 pub struct SettingParentMarker(SettingName);
 
@@ -73,9 +235,13 @@ pub struct RedrawSetting(SettingName);
 #[derive(Event)]
 pub struct UpdateSetting(SettingName);
 
-#[derive(Component, Debug, Clone, PartialEq, Eq, Copy, Enum)]
+#[derive(Component, Debug, Clone, PartialEq, Eq, Copy, Enum, Serialize, Deserialize)]
 pub enum SettingName {
     Volume,
+    MovementPreset,
+    SnapAngles,
+    AimAssist,
+    PreviewScale,
 }
 
 fn init_settings() -> Settings {
@@ -91,29 +257,88 @@ fn init_settings() -> Settings {
             100.0,
             0.0..=100.0,
             "%".to_owned(),
-        )
+        ),
+        // the presets are filled in once the movement tuning is loaded
+        SettingName::MovementPreset => Setting::new_choice(
+            "Movement".to_owned(),
+            0,
+            vec![DEFAULT_PRESET.to_owned()],
+        ),
+        // see Settings::snap_increments
+        SettingName::SnapAngles => Setting::new_choice(
+            "Snap Angles".to_owned(),
+            1,
+            SNAP_ANGLES_OPTIONS
+                .iter()
+                .map(|angles| angles.map_or("Free".to_owned(), |angles| angles.to_string()))
+                .collect(),
+        ),
+        // see Settings::aim_assist
+        SettingName::AimAssist => Setting::new_choice(
+            "Aim Assist".to_owned(),
+            0,
+            AIM_ASSIST_OPTIONS
+                .iter()
+                .map(|(_, label)| (*label).to_owned())
+                .collect(),
+        ),
+        // pixels per level cell of the level select previews, see level_preview::preview_scale
+        SettingName::PreviewScale => Setting::new_choice(
+            "Preview Scale".to_owned(),
+            3,
+            vec!["1".to_owned(), "2".to_owned(), "4".to_owned(), "8".to_owned()],
+        ),
     })
 }
 
+/// Loads the [`Settings`] stored by a previous session, falling back to the defaults from
+/// [`init_settings`].
+fn load_settings() -> Settings {
+    let mut settings = init_settings();
+    let stored = match storage::read(SETTINGS_FILE_NAME) {
+        Ok(Some(contents)) => contents,
+        Ok(None) => return settings,
+        Err(err) => {
+            error!("Could not read settings: {err}");
+            return settings;
+        }
+    };
+    match toml::from_str::<toml::Table>(&stored) {
+        Ok(stored) => settings.apply_stored(&stored),
+        Err(err) => error!("Could not parse settings: {err}"),
+    }
+    settings
+}
+
 impl Plugin for SettingsPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             PostUpdate,
-            switch_to_settings.run_if(input_just_pressed(KeyCode::Comma)),
+            switch_to_settings.run_if(input_just_pressed(InputAction::Settings)),
         )
-        .insert_resource(init_settings())
+        .insert_resource(load_settings())
         .add_event::<RedrawSetting>()
         .add_event::<UpdateSetting>()
+        .add_systems(Startup, apply_loaded_settings)
         .add_systems(
             FixedUpdate,
             (
                 spawn_settings.run_if(in_state(UiState::Settings)),
                 handle_slider_buttons.run_if(in_state(UiState::Settings)),
+                handle_choice_buttons.run_if(in_state(UiState::Settings)),
+                (handle_rebind_buttons, handle_reset_bindings_button)
+                    .run_if(in_state(UiState::Settings)),
+                update_rebind_buttons
+                    .after(handle_rebind_buttons)
+                    .after(handle_reset_bindings_button)
+                    .run_if(in_state(UiState::Settings))
+                    .run_if(resource_changed::<InputRebinding>.or(resource_changed::<Config>)),
                 despawn_settings
                     .after(handle_move_camera)
                     .run_if(not(in_state(UiState::Settings))),
                 (redraw_setting, update_setting)
                     .after(handle_slider_buttons)
+                    .after(handle_choice_buttons)
                     .run_if(in_state(UiState::Settings)),
             ),
         );
@@ -133,6 +358,8 @@ fn spawn_settings(
     level_select_ui_query: Query<Entity, With<SettingsUiMarker>>,
     asset_server: Res<AssetServer>,
     settings: Res<Settings>,
+    config: Res<Config>,
+    rebinding: Res<InputRebinding>,
     mut ev_change_bgm: EventWriter<ChangeBgmEvent>,
 ) {
     if level_select_ui_query.get_single().is_ok() {
@@ -165,6 +392,37 @@ fn spawn_settings(
                 .id()
         })
         .collect::<Vec<_>>();
+    let binding_nodes = (0..InputAction::LENGTH)
+        .map(|i| {
+            let action = InputAction::from_usize(i);
+            commands
+                .spawn(Node {
+                    width: Val::Percent(100.0),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(action.display_name()),
+                        font.clone().with_font_size(16.0),
+                    ));
+                    parent.spawn((
+                        Node {
+                            padding: UiRect::horizontal(Val::Px(4.0)),
+                            ..default()
+                        },
+                        Button,
+                        Text::new(binding_text(action, &config, &rebinding)),
+                        font.clone().with_font_size(16.0),
+                        BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                        RebindButton(action),
+                    ));
+                })
+                .id()
+        })
+        .collect::<Vec<_>>();
     commands
         .spawn((
             SettingsUiMarker,
@@ -192,7 +450,34 @@ fn spawn_settings(
 
                     ..default()
                 })
-                .add_children(&setting_nodes);
+                .add_children(&setting_nodes)
+                .with_children(|parent| {
+                    parent
+                        .spawn(Node {
+                            width: Val::Percent(100.0),
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            margin: UiRect::top(Val::Px(16.0)),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent
+                                .spawn((Text::new("Controls"), font.clone().with_font_size(24.0)));
+                            parent.spawn((
+                                Node {
+                                    padding: UiRect::horizontal(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                Button,
+                                Text::new("Reset"),
+                                font.clone().with_font_size(24.0),
+                                BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                                ResetBindingsButton,
+                            ));
+                        });
+                })
+                .add_children(&binding_nodes);
         });
 }
 
@@ -266,6 +551,48 @@ fn spawn_setting_children(
                     SliderButton(10.0),
                 ));
             }
+            SettingVariant::Choice { value, options } => {
+                let choice_button_bundle = (
+                    Node {
+                        align_content: AlignContent::Center,
+                        padding: UiRect {
+                            left: Val::Px(4.0),
+                            right: Val::Px(4.0),
+                            top: Val::Px(0.0),
+                            bottom: Val::Px(0.0),
+                        },
+                        ..default()
+                    },
+                    Button,
+                    font.clone().with_font_size(24.0),
+                    settings_index,
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                );
+                parent.spawn((
+                    choice_button_bundle.clone(),
+                    Text::new("<"),
+                    ChoiceButton(-1),
+                ));
+
+                parent
+                    .spawn((Node {
+                        width: Val::Px(100.0),
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Row,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },))
+                    .with_child((
+                        Text::new(options.get(value.value).cloned().unwrap_or_default()),
+                        font.clone().with_font_size(24.0),
+                    ));
+
+                parent.spawn((
+                    choice_button_bundle.clone(),
+                    Text::new(">"),
+                    ChoiceButton(1),
+                ));
+            }
         });
 }
 
@@ -297,7 +624,10 @@ fn handle_slider_buttons(
                 ref mut value,
                 ref range,
                 ..
-            } = setting.variant;
+            } = setting.variant
+            else {
+                continue;
+            };
 
             value.value += slider_button.0;
             value.value = value.value.clamp(*range.start(), *range.end());
@@ -308,6 +638,90 @@ fn handle_slider_buttons(
     }
 }
 
+#[allow(clippy::type_complexity)]
+fn handle_choice_buttons(
+    interaction_query: Query<
+        (&Interaction, &ChoiceButton, &SettingName),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut settings: ResMut<Settings>,
+    mut redraw_ev: EventWriter<RedrawSetting>,
+    mut update_ev: EventWriter<UpdateSetting>,
+) {
+    for (interaction, choice_button, setting_name) in interaction_query.iter() {
+        if interaction == &Interaction::Pressed {
+            let setting = &mut settings.0[*setting_name];
+            let SettingVariant::Choice {
+                ref mut value,
+                ref options,
+            } = setting.variant
+            else {
+                continue;
+            };
+
+            // wrap around in both directions
+            value.value = (value.value as isize + choice_button.0)
+                .rem_euclid(options.len().max(1) as isize) as usize;
+
+            redraw_ev.send(RedrawSetting(*setting_name));
+            update_ev.send(UpdateSetting(*setting_name));
+        }
+    }
+}
+
+fn binding_text(action: InputAction, config: &Config, rebinding: &InputRebinding) -> String {
+    if rebinding.0 == Some(action) {
+        return "Press a key...".to_owned();
+    }
+    let bindings = config.controls_config.bindings.get(action);
+    if bindings.is_empty() {
+        return "Unbound".to_owned();
+    }
+    bindings
+        .iter()
+        .map(InputBinding::display_name)
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+#[allow(clippy::type_complexity)]
+fn handle_rebind_buttons(
+    interaction_query: Query<(&Interaction, &RebindButton), (Changed<Interaction>, With<Button>)>,
+    mut rebinding: ResMut<InputRebinding>,
+) {
+    for (interaction, rebind_button) in interaction_query.iter() {
+        if interaction == &Interaction::Pressed {
+            rebinding.0 = Some(rebind_button.0);
+        }
+    }
+}
+
+fn handle_reset_bindings_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ResetBindingsButton>)>,
+    mut config: ResMut<Config>,
+    mut stored_bindings: ResMut<StoredBindings>,
+    mut rebinding: ResMut<InputRebinding>,
+) {
+    for interaction in interaction_query.iter() {
+        if interaction == &Interaction::Pressed {
+            config.controls_config.bindings = InputBindings::default();
+            stored_bindings.0.clear();
+            stored_bindings.save();
+            rebinding.0 = None;
+        }
+    }
+}
+
+fn update_rebind_buttons(
+    mut q_rebind_buttons: Query<(&RebindButton, &mut Text)>,
+    config: Res<Config>,
+    rebinding: Res<InputRebinding>,
+) {
+    for (rebind_button, mut text) in q_rebind_buttons.iter_mut() {
+        text.0 = binding_text(rebind_button.0, &config, &rebinding);
+    }
+}
+
 fn redraw_setting(
     mut commands: Commands,
     mut ev: EventReader<RedrawSetting>,
@@ -336,18 +750,96 @@ fn redraw_setting(
     }
 }
 
+fn apply_setting(setting_name: SettingName, settings: &Settings, global_volume: &mut GlobalVolume) {
+    let setting = &settings.0[setting_name];
+    match setting_name {
+        SettingName::Volume => {
+            let SettingVariant::Slider { ref value, .. } = setting.variant else {
+                return;
+            };
+            global_volume.volume = Volume::new(value.value / 100.0);
+        }
+        // read directly from the settings where they are used
+        SettingName::MovementPreset
+        | SettingName::SnapAngles
+        | SettingName::AimAssist
+        | SettingName::PreviewScale => {}
+    }
+}
+
+/// [`System`] that applies the [`Settings`] loaded by [`load_settings`] at startup. `--mute` only
+/// overrides the volume until it is changed, and isn't written to the settings file.
+fn apply_loaded_settings(
+    settings: Res<Settings>,
+    cli_args: Res<CliArgs>,
+    mut global_volume: ResMut<GlobalVolume>,
+) {
+    for setting_name in (0..SettingName::LENGTH).map(SettingName::from_usize) {
+        apply_setting(setting_name, &settings, &mut global_volume);
+    }
+    if cli_args.mute {
+        global_volume.volume = Volume::ZERO;
+    }
+}
+
 fn update_setting(
     mut ev: EventReader<UpdateSetting>,
     settings: Res<Settings>,
     mut global_volume: ResMut<GlobalVolume>,
 ) {
+    let mut changed = false;
     for UpdateSetting(setting_name) in ev.read() {
-        let setting = &settings.0[SettingName::Volume];
-        match setting_name {
-            SettingName::Volume => {
-                let SettingVariant::Slider { ref value, .. } = setting.variant;
-                global_volume.volume = Volume::new(value.value / 100.0);
-            }
-        }
+        apply_setting(*setting_name, &settings, &mut global_volume);
+        changed = true;
+    }
+    if changed {
+        settings.write();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slider_value(settings: &Settings, setting_name: SettingName) -> f32 {
+        let SettingVariant::Slider { value, .. } = &settings.0[setting_name].variant else {
+            panic!("{setting_name:?} is not a slider");
+        };
+        value.value
+    }
+
+    #[test]
+    fn stored_settings_round_trip() {
+        let mut settings = init_settings();
+        settings.apply_stored(&toml::from_str("Volume = 40\nSnapAngles = \"32\"").unwrap());
+        assert_eq!(slider_value(&settings, SettingName::Volume), 40.0);
+        assert_eq!(settings.choice(SettingName::SnapAngles), Some("32"));
+
+        let mut reloaded = init_settings();
+        reloaded.apply_stored(&settings.to_stored());
+        assert_eq!(slider_value(&reloaded, SettingName::Volume), 40.0);
+        assert_eq!(reloaded.choice(SettingName::SnapAngles), Some("32"));
+    }
+
+    #[test]
+    fn invalid_stored_settings_are_skipped() {
+        let mut settings = init_settings();
+        settings.apply_stored(
+            &toml::from_str("Volume = 250.0\nSnapAngles = \"7\"\nBrightness = 3").unwrap(),
+        );
+        assert_eq!(slider_value(&settings, SettingName::Volume), 100.0);
+        assert_eq!(settings.choice(SettingName::SnapAngles), Some("16"));
+    }
+
+    #[test]
+    fn aim_settings_are_typed() {
+        let mut settings = init_settings();
+        assert_eq!(settings.snap_increments(), Some(16));
+        assert!(!settings.aim_assist());
+
+        settings
+            .apply_stored(&toml::from_str("SnapAngles = \"Free\"\nAimAssist = \"On\"").unwrap());
+        assert_eq!(settings.snap_increments(), None);
+        assert!(settings.aim_assist());
     }
 }
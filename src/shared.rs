@@ -46,6 +46,7 @@ pub enum UiState {
     LevelSelect,
     Settings,
     StartMenu,
+    LevelPacks,
 }
 
 #[derive(Event, PartialEq, Eq)]
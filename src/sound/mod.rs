@@ -5,6 +5,8 @@ use bevy::{
     prelude::*,
 };
 
+use crate::level::entity::LdtkEntityError;
+
 pub struct SoundPlugin;
 
 impl Plugin for SoundPlugin {
@@ -39,7 +41,7 @@ impl FromWorld for BgmTracks {
     }
 }
 
-#[derive(Default, PartialEq, Eq, Clone, Copy)]
+#[derive(Default, PartialEq, Eq, Clone, Copy, Debug)]
 pub enum BgmTrack {
     MustntStop,
     LightInTheDark,
@@ -49,6 +51,23 @@ pub enum BgmTrack {
     None,
 }
 
+/// Parses the `Bgm` level field, see [`LevelMetadata`](crate::level::metadata::LevelMetadata).
+impl TryFrom<&String> for BgmTrack {
+    type Error = LdtkEntityError;
+
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "MustntStop" => Ok(BgmTrack::MustntStop),
+            "LightInTheDark" => Ok(BgmTrack::LightInTheDark),
+            "Cutscene1Draft" => Ok(BgmTrack::Cutscene1Draft),
+            "None" => Ok(BgmTrack::None),
+            _ => Err(LdtkEntityError::new(format!(
+                "String {value} does not represent a Bgm track"
+            ))),
+        }
+    }
+}
+
 pub const BGM_VOLUME: f32 = 0.8;
 
 /// Fades out all other bgm tracks, and spawns the selected track
@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelIid;
+
+use crate::{
+    camera::{setup_camera, MainCamera},
+    config::Config,
+    level::level_completion::LevelCompletionEvent,
+    level_pack::LevelPacks,
+    save::SaveData,
+    shared::{GameState, UiState},
+};
+
+/// [`Plugin`] for the speedrun timer, which runs from the first `StartMarker` the player touches
+/// and splits at every `EndMarker`. Picking a level in the level select ends the run.
+pub struct SpeedrunPlugin;
+
+impl Plugin for SpeedrunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpeedrunTimer>()
+            .add_systems(Startup, spawn_speedrun_hud.after(setup_camera))
+            .add_systems(OnEnter(UiState::LevelSelect), reset_speedrun_timer)
+            .add_systems(
+                FixedUpdate,
+                tick_speedrun_timer
+                    .run_if(not(in_state(GameState::Paused)).and(not(in_state(GameState::Ui)))),
+            )
+            .add_systems(
+                Update,
+                (handle_speedrun_splits, update_speedrun_hud).chain(),
+            );
+    }
+}
+
+/// A completed level of a speedrun.
+#[derive(Debug, Clone)]
+pub struct Split {
+    /// The number of [`FixedUpdate`] steps it took to complete the level.
+    pub ticks: u32,
+    /// The personal best of the level before this split, if there was one.
+    pub previous_best: Option<u32>,
+}
+
+/// [`Resource`] for the speedrun timer. Time is counted in [`FixedUpdate`] steps so that results
+/// don't depend on the frame rate, and don't count while the game is paused or in a menu.
+#[derive(Resource, Default, Debug)]
+pub struct SpeedrunTimer {
+    running: bool,
+    /// The number of [`FixedUpdate`] steps since the run started.
+    ticks: u32,
+    /// The level being run, and the tick its `StartMarker` was left at.
+    level: Option<(LevelIid, u32)>,
+    pub splits: Vec<Split>,
+}
+
+impl SpeedrunTimer {
+    /// The number of [`FixedUpdate`] steps spent in the level being run.
+    pub fn level_ticks(&self) -> Option<u32> {
+        self.level.as_ref().map(|(_, start)| self.ticks - start)
+    }
+}
+
+/// Formats a number of [`FixedUpdate`] steps as `m:ss.mmm`.
+pub fn format_ticks(ticks: u32, timestep: Duration) -> String {
+    let millis = (timestep * ticks).as_millis();
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+fn tick_speedrun_timer(mut timer: ResMut<SpeedrunTimer>) {
+    if timer.running {
+        timer.ticks += 1;
+    }
+}
+
+/// [`System`] that stops the timer and throws away the splits of the run.
+fn reset_speedrun_timer(mut timer: ResMut<SpeedrunTimer>) {
+    *timer = SpeedrunTimer::default();
+}
+
+/// [`System`] that starts the timer and the level being run at `StartMarker`s, and splits at
+/// `EndMarker`s. New personal bests go into the [`SaveData`], which is written by
+/// [`save_progress`](crate::save::save_progress), unless every level is unlocked for debugging.
+pub fn handle_speedrun_splits(
+    mut ev_completion: EventReader<LevelCompletionEvent>,
+    mut timer: ResMut<SpeedrunTimer>,
+    mut save_data: ResMut<SaveData>,
+    level_packs: Res<LevelPacks>,
+    config: Res<Config>,
+) {
+    for event in ev_completion.read() {
+        match event {
+            LevelCompletionEvent::Started(level_iid) => {
+                if !timer.running {
+                    timer.running = true;
+                    timer.ticks = 0;
+                }
+                timer.level = Some((level_iid.clone(), timer.ticks));
+            }
+            LevelCompletionEvent::Completed(level_iid) => {
+                // only the level whose start the player left counts
+                if timer
+                    .level
+                    .as_ref()
+                    .is_none_or(|(run_level, _)| run_level != level_iid)
+                {
+                    continue;
+                }
+                let Some(ticks) = timer.level_ticks() else {
+                    continue;
+                };
+                timer.level = None;
+                let pack_path = &level_packs.active().path;
+                let previous_best = save_data.personal_best(pack_path, level_iid);
+                // like the progress, runs with every level unlocked don't go into the save
+                if !config.debug_config.unlock_levels
+                    && previous_best.is_none_or(|best| ticks < best)
+                {
+                    save_data
+                        .personal_bests
+                        .entry(pack_path.clone())
+                        .or_default()
+                        .insert(level_iid.as_str().to_owned(), ticks);
+                }
+                timer.splits.push(Split {
+                    ticks,
+                    previous_best,
+                });
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct SpeedrunHudMarker;
+
+fn spawn_speedrun_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    q_main_camera: Query<Entity, With<MainCamera>>,
+) {
+    let Ok(main_camera) = q_main_camera.get_single() else {
+        return;
+    };
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                padding: UiRect::all(Val::Vw(1.)),
+                ..default()
+            },
+            // spawn underneath the level select UI
+            GlobalZIndex(-1),
+            // show underneath screen transitions
+            TargetCamera(main_camera),
+        ))
+        .with_child((
+            Text::default(),
+            TextFont {
+                font: asset_server.load("fonts/Munro.ttf"),
+                font_size: 20.,
+                ..default()
+            },
+            SpeedrunHudMarker,
+        ));
+}
+
+fn update_speedrun_hud(
+    timer: Res<SpeedrunTimer>,
+    fixed_time: Res<Time<Fixed>>,
+    mut q_hud: Query<&mut Text, With<SpeedrunHudMarker>>,
+) {
+    let Ok(mut text) = q_hud.get_single_mut() else {
+        return;
+    };
+    if !timer.running {
+        text.0.clear();
+        return;
+    }
+    let timestep = fixed_time.timestep();
+
+    let mut hud = format_ticks(timer.ticks, timestep);
+    if let Some(level_ticks) = timer.level_ticks() {
+        hud += &format!("\nLevel {}", format_ticks(level_ticks, timestep));
+    }
+    if let Some(split) = timer.splits.last() {
+        hud += &format!("\nSplit {}", format_ticks(split.ticks, timestep));
+        if let Some(best) = split.previous_best {
+            let (sign, diff) = if split.ticks <= best {
+                ('-', best - split.ticks)
+            } else {
+                ('+', split.ticks - best)
+            };
+            hud += &format!(" ({sign}{})", format_ticks(diff, timestep));
+        }
+    }
+    if text.0 != hud {
+        text.0 = hud;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_are_formatted_as_minutes_seconds_millis() {
+        let timestep = Duration::from_millis(10);
+        assert_eq!(format_ticks(0, timestep), "0:00.000");
+        assert_eq!(format_ticks(1234, timestep), "0:12.340");
+        assert_eq!(format_ticks(6150, timestep), "1:01.500");
+    }
+}
@@ -1,22 +1,47 @@
 use bevy::{prelude::*, ui::widget::NodeImageMode};
 
-use crate::shared::UiState;
+use crate::{
+    level_select::{Levels, StartLevel},
+    player::ghost::BestRuns,
+    save::{ActiveSaveSlot, SaveData, SAVE_SLOTS},
+    shared::UiState,
+};
 
 pub struct StartMenuPlugin;
 
 impl Plugin for StartMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, spawn_start.run_if(in_state(UiState::StartMenu)))
-            .add_systems(Update, exit_start.run_if(not(in_state(UiState::StartMenu))))
-            .add_systems(Update, start_game);
+        app.add_systems(
+            Update,
+            spawn_start
+                .run_if(in_state(UiState::StartMenu))
+                // `--level` skips the start menu
+                .run_if(not(resource_exists::<StartLevel>)),
+        )
+        .add_systems(Update, exit_start.run_if(not(in_state(UiState::StartMenu))))
+        .add_systems(
+            Update,
+            handle_save_slot_buttons.run_if(in_state(UiState::StartMenu)),
+        );
     }
 }
 
 #[derive(Component)]
 pub struct StartMenuMarker;
 
-#[derive(Component)]
-pub struct StartMenuButtonMarker;
+/// [`Component`] for the buttons of the save slot picker.
+#[derive(Component, Clone, Copy, Debug)]
+enum SaveSlotButton {
+    Play(usize),
+    Copy(usize),
+    Delete(usize),
+}
+
+/// Formats a play time as `h:mm:ss`.
+fn format_play_time(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
 
 fn spawn_start(
     mut commands: Commands,
@@ -26,6 +51,22 @@ fn spawn_start(
     if q_start_menu.get_single().is_ok() {
         return;
     };
+    let font = TextFont {
+        font: asset_server.load("fonts/Munro.ttf"),
+        font_size: 20.,
+        ..default()
+    };
+    let button_node = Node {
+        padding: UiRect::all(Val::Px(8.0)),
+        border: UiRect::all(Val::Px(2.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_colors = (
+        BorderColor(Color::WHITE),
+        BackgroundColor(Color::BLACK.with_alpha(0.6)),
+    );
 
     commands
         .spawn((
@@ -38,17 +79,75 @@ fn spawn_start(
             ImageNode::from(asset_server.load("ui/start.png")).with_mode(NodeImageMode::Stretch),
             StartMenuMarker,
         ))
-        .with_child((
-            Node {
-                width: Val::Px(120.),
-                height: Val::Px(60.),
-                top: Val::Percent(60.),
-                ..default()
-            },
-            ImageNode::from(asset_server.load("ui/start_button.png")),
-            Button,
-            StartMenuButtonMarker,
-        ));
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    top: Val::Percent(45.),
+                    height: Val::Auto,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for slot in 0..SAVE_SLOTS {
+                        let save_data = SaveData::load(slot);
+                        let summary = match &save_data {
+                            Ok(Some(save_data)) => format!(
+                                "{:.0}% - {} - {}",
+                                save_data.completion() * 100.,
+                                format_play_time(save_data.play_time_secs),
+                                save_data.last_level.as_deref().unwrap_or("-"),
+                            ),
+                            Ok(None) => "Empty".to_owned(),
+                            Err(err) => {
+                                error!("Could not load save slot {}: {err}", slot + 1);
+                                "Unreadable".to_owned()
+                            }
+                        };
+
+                        parent
+                            .spawn(Node {
+                                column_gap: Val::Px(8.0),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent
+                                    .spawn((
+                                        Button,
+                                        Node {
+                                            width: Val::Px(280.0),
+                                            flex_direction: FlexDirection::Column,
+                                            ..button_node.clone()
+                                        },
+                                        button_colors,
+                                        SaveSlotButton::Play(slot),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new(format!("Slot {}", slot + 1)),
+                                            font.clone().with_font_size(24.),
+                                        ));
+                                        parent.spawn((Text::new(summary), font.clone()));
+                                    });
+                                if matches!(save_data, Ok(None)) {
+                                    return;
+                                }
+                                // an unreadable save can't be played or copied, only deleted
+                                let copy = save_data
+                                    .is_ok()
+                                    .then_some(("Copy", SaveSlotButton::Copy(slot)));
+                                for (label, button) in copy
+                                    .into_iter()
+                                    .chain([("Delete", SaveSlotButton::Delete(slot))])
+                                {
+                                    parent
+                                        .spawn((Button, button_node.clone(), button_colors, button))
+                                        .with_child((Text::new(label), font.clone()));
+                                }
+                            });
+                    }
+                });
+        });
 }
 
 fn exit_start(mut commands: Commands, query: Query<Entity, With<StartMenuMarker>>) {
@@ -58,14 +157,96 @@ fn exit_start(mut commands: Commands, query: Query<Entity, With<StartMenuMarker>
     commands.entity(entity).despawn_recursive();
 }
 
-fn start_game(
-    q_button: Query<&Interaction, (With<StartMenuButtonMarker>, Changed<Interaction>)>,
+/// [`System`] that starts the game in the chosen save slot, or copies or deletes a slot. Deleting
+/// has to be confirmed by pressing the button a second time.
+#[allow(clippy::too_many_arguments)]
+fn handle_save_slot_buttons(
+    mut commands: Commands,
+    q_buttons: Query<(&Interaction, &SaveSlotButton, &Children), Changed<Interaction>>,
+    mut q_text: Query<&mut Text>,
+    q_start_menu: Query<Entity, With<StartMenuMarker>>,
+    mut active_slot: ResMut<ActiveSaveSlot>,
+    mut levels: ResMut<Levels>,
     mut next_state: ResMut<NextState<UiState>>,
+    mut pending_delete: Local<Option<usize>>,
 ) {
-    let Ok(interaction) = &q_button.get_single() else {
-        return;
-    };
-    if **interaction == Interaction::Pressed {
-        next_state.set(UiState::LevelSelect);
+    let mut respawn_menu = false;
+    for (interaction, button, children) in q_buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            SaveSlotButton::Play(slot) => {
+                let save_data = match SaveData::load(slot) {
+                    Ok(save_data) => save_data.unwrap_or_default(),
+                    // playing would overwrite the unreadable save with an empty one
+                    Err(err) => {
+                        error!("Not playing save slot {}: {err}", slot + 1);
+                        continue;
+                    }
+                };
+                // the ghosts aren't worth refusing to play over, a new best run overwrites them
+                let best_runs = BestRuns::load(slot).unwrap_or_else(|err| {
+                    error!("Ignoring the ghosts of save slot {}: {err}", slot + 1);
+                    BestRuns::default()
+                });
+                commands.insert_resource(save_data);
+                commands.insert_resource(best_runs);
+                active_slot.0 = Some(slot);
+                // `init_levels` rebuilds the levels from the new save data
+                levels.0.clear();
+                next_state.set(UiState::LevelSelect);
+            }
+            SaveSlotButton::Copy(slot) => {
+                let empty_slot =
+                    (0..SAVE_SLOTS).find(|other| matches!(SaveData::load(*other), Ok(None)));
+                let Some(empty_slot) = empty_slot else {
+                    warn!("There is no empty save slot to copy slot {} to", slot + 1);
+                    continue;
+                };
+                let copied = SaveData::load(slot)
+                    .and_then(|save_data| save_data.unwrap_or_default().write(empty_slot));
+                if let Err(err) = copied {
+                    error!("Could not copy save slot {}: {err}", slot + 1);
+                } else {
+                    let copied =
+                        BestRuns::load(slot).and_then(|best_runs| best_runs.write(empty_slot));
+                    if let Err(err) = copied {
+                        error!("Could not copy the ghosts of save slot {}: {err}", slot + 1);
+                    }
+                }
+                respawn_menu = true;
+            }
+            SaveSlotButton::Delete(slot) if *pending_delete == Some(slot) => {
+                if let Err(err) = SaveData::delete(slot) {
+                    error!("Could not delete save slot {}: {err}", slot + 1);
+                }
+                if let Err(err) = BestRuns::delete(slot) {
+                    error!(
+                        "Could not delete the ghosts of save slot {}: {err}",
+                        slot + 1
+                    );
+                }
+                respawn_menu = true;
+            }
+            SaveSlotButton::Delete(slot) => {
+                *pending_delete = Some(slot);
+                if let Some(mut text) = children
+                    .first()
+                    .and_then(|child| q_text.get_mut(*child).ok())
+                {
+                    text.0 = "Sure?".to_owned();
+                }
+                continue;
+            }
+        }
+        *pending_delete = None;
+    }
+
+    // refresh the slot summaries by spawning the start menu again
+    if respawn_menu {
+        if let Ok(start_menu) = q_start_menu.get_single() {
+            commands.entity(start_menu).despawn_recursive();
+        }
     }
 }
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::{
+    camera::MainCamera,
+    level::{metadata::LevelMetadataCache, CurrentLevel},
+};
+
+/// How long a title card is shown for, including fading in and out.
+const TITLE_CARD_SECS: f32 = 4.0;
+const TITLE_CARD_FADE_SECS: f32 = 1.0;
+
+/// [`Plugin`] that shows the title of a chapter when the player enters a level in a different
+/// chapter than the last one, see [`LevelMetadata`](crate::level::metadata::LevelMetadata).
+pub struct TitleCardPlugin;
+
+impl Plugin for TitleCardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                show_title_card.run_if(resource_changed::<CurrentLevel>),
+                fade_title_card,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Component)]
+struct TitleCard(Timer);
+
+fn show_title_card(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    level_metadata: Res<LevelMetadataCache>,
+    asset_server: Res<AssetServer>,
+    q_main_camera: Query<Entity, With<MainCamera>>,
+    q_title_card: Query<Entity, With<TitleCard>>,
+    mut last_chapter: Local<Option<i32>>,
+) {
+    let Some(metadata) = level_metadata.get(&current_level.level_iid) else {
+        return;
+    };
+    if metadata.chapter == *last_chapter {
+        return;
+    }
+    *last_chapter = metadata.chapter;
+    let Some(title) = metadata.chapter_title() else {
+        return;
+    };
+    let Ok(main_camera) = q_main_camera.get_single() else {
+        return;
+    };
+
+    for title_card in q_title_card.iter() {
+        commands.entity(title_card).despawn_recursive();
+    }
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            // spawn underneath the level select UI
+            GlobalZIndex(-1),
+            // show underneath screen transitions
+            TargetCamera(main_camera),
+            TitleCard(Timer::from_seconds(TITLE_CARD_SECS, TimerMode::Once)),
+        ))
+        .with_child((
+            Text::new(title),
+            TextFont {
+                font: asset_server.load("fonts/Munro.ttf"),
+                font_size: 48.,
+                ..default()
+            },
+            TextColor(Color::WHITE.with_alpha(0.0)),
+        ));
+}
+
+/// [`System`] that fades title cards in and out, and despawns them once they are done.
+fn fade_title_card(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_title_card: Query<(Entity, &mut TitleCard, &Children)>,
+    mut q_text_color: Query<&mut TextColor>,
+) {
+    for (entity, mut title_card, children) in q_title_card.iter_mut() {
+        title_card.0.tick(time.delta());
+        if title_card.0.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let elapsed = title_card.0.elapsed_secs();
+        let alpha = (elapsed.min(TITLE_CARD_SECS - elapsed) / TITLE_CARD_FADE_SECS).min(1.0);
+        for child in children.iter() {
+            if let Ok(mut text_color) = q_text_color.get_mut(*child) {
+                text_color.0.set_alpha(alpha);
+            }
+        }
+    }
+}